@@ -16,9 +16,10 @@
 
 use std::{fmt::Display, str::FromStr};
 
+use clap::ValueEnum;
 use either::Either::{self, Left, Right};
 
-use crate::{LprsError, LprsResult};
+use crate::{LprsError, LprsResult, vault::custom_field::FieldKind};
 
 /// Parse the key & value arguments.
 ///
@@ -36,6 +37,29 @@ pub fn kv_parser(value: &str) -> LprsResult<(String, Option<String>)> {
     }
 }
 
+/// Parse the custom field kind arguments, `KEY=KIND`.
+///
+/// ## Errors
+/// - If the argument value syntax not `KEY=KIND`
+/// - If the kind is unknown
+pub fn field_kind_parser(value: &str) -> LprsResult<(String, FieldKind)> {
+    let Some((key, kind)) = value
+        .split_once('=')
+        .filter(|(key, _)| !key.trim().is_empty())
+    else {
+        return Err(LprsError::ArgParse(
+            "Invalid field kind, the syntax is `KEY=KIND`".to_owned(),
+        ));
+    };
+    let kind = <FieldKind as ValueEnum>::from_str(kind.trim(), true).map_err(|_| {
+        LprsError::ArgParse(format!(
+            "Invalid field kind `{}`, can be [text, secret, url, email, totp, multiline]",
+            kind.trim()
+        ))
+    })?;
+    Ok((key.trim().to_owned(), kind))
+}
+
 /// Parse `Either` type arguments.
 ///
 /// ## Errors
@@ -59,3 +83,33 @@ where
                 .map(Right)
         })
 }
+
+/// Parse the command argument key, `command.argument`
+///
+/// ## Errors
+/// - If the argument value syntax not `command.argument`
+pub fn config_key_parser(value: &str) -> LprsResult<(String, String)> {
+    value
+        .trim()
+        .split_once('.')
+        .map(|(command, argument)| (command.trim().to_owned(), argument.trim().to_owned()))
+        .filter(|(command, argument)| !command.is_empty() && !argument.is_empty())
+        .ok_or_else(|| {
+            LprsError::ArgParse("Invalid key, the syntax is `COMMAND.ARGUMENT`".to_owned())
+        })
+}
+
+/// Parse the command argument default, `command.argument=value`. The value is
+/// a TOML value, or a string if it's not a valid one
+///
+/// ## Errors
+/// - If the argument value syntax not `command.argument=value`
+pub fn default_parser(value: &str) -> LprsResult<(String, String, toml::Value)> {
+    let Some((key, value)) = value.split_once('=') else {
+        return Err(LprsError::ArgParse(
+            "Invalid default, the syntax is `COMMAND.ARGUMENT=VALUE`".to_owned(),
+        ));
+    };
+    let (command, argument) = config_key_parser(key)?;
+    Ok((command, argument, crate::config::parse_value(value.trim())))
+}
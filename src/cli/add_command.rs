@@ -14,6 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
 
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::PathBuf,
+};
+
 use clap::Args;
 
 use crate::{
@@ -21,8 +26,20 @@ use crate::{
     LprsError,
     LprsResult,
     clap_parsers,
+    cli::{gen_command::GeneratedPassword, wizard::VaultWizard},
+    config::policies::PoliciesConfig,
+    generator::GeneratorSpec,
     utils,
-    vault::{Vault, Vaults, cipher},
+    vault::{
+        Vault,
+        Vaults,
+        cipher,
+        custom_field::FieldKind,
+        item::ItemArgs,
+        otpauth::OtpAuth,
+        qr,
+        strength::StrengthPolicy,
+    },
 };
 
 #[derive(Debug, Args)]
@@ -34,22 +51,63 @@ pub struct Add {
     #[arg(short, long)]
     #[allow(clippy::option_option)]
     password:      Option<Option<String>>,
+    /// Generate the password, with the generator spec `LENGTH[:CLASSES]`,
+    /// `words:N[:SEPARATOR]` or `pattern:TEMPLATE`. By default 18 characters of
+    /// all the classes
+    #[arg(
+        short,
+        long,
+        value_name = "SPEC",
+        require_equals = true,
+        conflicts_with = "password"
+    )]
+    #[allow(clippy::option_option)]
+    generate:      Option<Option<GeneratorSpec>>,
+    #[command(flatten)]
+    generated:     GeneratedPassword,
     /// The TOTP secret, if there is no value you will prompt it
     #[arg(short, long)]
     #[allow(clippy::option_option)]
     totp_secret:   Option<Option<String>>,
+    /// Read the TOTP secret, hash function and digits from a QR code PNG
+    /// image, e.g. the one shown by the service when enabling the 2FA
+    #[arg(long, value_name = "PNG_PATH", conflicts_with_all = ["totp_secret", "totp_hash", "totp_digits"])]
+    totp_qr:       Option<PathBuf>,
+    /// Read the note from the given file as it is, e.g. a multi-line note
+    #[arg(long, value_name = "PATH", conflicts_with = "note")]
+    note_file:     Option<PathBuf>,
     /// Add a custom field to the vault
     ///
     /// If there is no value, you will enter it through a prompt
     #[arg(name = "KEY(=VALUE)?", short = 'c', long = "custom")]
     #[arg(value_parser = clap_parsers::kv_parser)]
     custom_fields: Vec<(String, Option<String>)>,
+    /// Set the kind of a custom field, `text`, `secret`, `url`, `email`,
+    /// `totp` or `multiline`. The custom fields are secrets by default
+    #[arg(long = "kind", value_name = "KEY=KIND")]
+    #[arg(value_parser = clap_parsers::field_kind_parser)]
+    field_kinds:   Vec<(String, FieldKind)>,
+    #[command(flatten)]
+    item:          ItemArgs,
+    #[command(flatten)]
+    strength:      StrengthPolicy,
     /// Force add, will not return error if there is a problem with the args.
     ///
-    /// For example, duplication in the custom fields and try to adding empty
-    /// vault
+    /// For example, duplication in the custom fields, try to adding empty
+    /// vault and a weak password
     #[arg(short, long)]
     force:         bool,
+    /// Walk through the vault fields with prompts, the given fields are the
+    /// initial values
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = [
+            "password", "generate", "totp_secret", "totp_qr", "KEY(=VALUE)?", "field_kinds",
+            "ItemArgs",
+        ]
+    )]
+    interactive:   bool,
 }
 
 impl Add {
@@ -57,13 +115,33 @@ impl Add {
     fn is_empty(&self) -> bool {
         self.vault_info.is_empty()
             && self.password.is_none()
+            && self.generate.is_none()
             && self.totp_secret.is_none()
+            && self.totp_qr.is_none()
+            && self.note_file.is_none()
             && self.custom_fields.is_empty()
+            && !self.item.has_fields()
+            && !self.interactive
     }
 }
 
 impl LprsCommand for Add {
     fn run(mut self, mut vault_manager: Vaults) -> LprsResult<()> {
+        self.vault_info.note =
+            utils::read_note(self.vault_info.note.take(), self.note_file.as_deref())?;
+        if self.vault_info.note.as_ref().is_some_and(String::is_empty) {
+            return Err(LprsError::EmptyValue);
+        }
+        if self.interactive {
+            VaultWizard {
+                generated: &self.generated,
+                strength:  &self.strength,
+                force:     self.force,
+            }
+            .run(&mut self.vault_info)?;
+            vault_manager.add_vault(self.vault_info);
+            return vault_manager.try_export();
+        }
         if !self.is_empty() {
             if let Some(totp_secret) = utils::user_secret(self.totp_secret, "TOTP Secret:", false)?
             {
@@ -72,12 +150,40 @@ impl LprsCommand for Add {
                 })?;
                 self.vault_info.totp_secret = Some(totp_secret);
             }
+            if let Some(ref qr_path) = self.totp_qr {
+                log::info!("Reading the TOTP QR code from: {}", qr_path.display());
+                let otp_auth = qr::read_png(qr_path)?.parse::<OtpAuth>()?;
+                otp_auth.check_supported()?;
+                self.vault_info.totp_secret = Some(otp_auth.secret);
+                self.vault_info.totp_hash = otp_auth.hash;
+                self.vault_info.totp_digits = otp_auth.digits;
+            }
 
             self.vault_info.name = self.vault_info.name.trim().to_string();
-            self.vault_info.password = utils::user_secret(self.password, "Vault password:", false)?;
-            self.vault_info.custom_fields = utils::prompt_custom(self.custom_fields)?
-                .into_iter()
-                .collect();
+            let password = if let Some(spec) = self.generate {
+                let mut spec = spec.unwrap_or_default();
+                if let Some(policy) = PoliciesConfig::load()?.vault_policy(&self.vault_info) {
+                    spec = spec.with_policy(policy.clone())?;
+                }
+                Some(self.generated.generate(&spec)?)
+            } else {
+                let prompted = matches!(self.password, Some(None));
+                let password = utils::user_secret(self.password, "Vault password:", false)?;
+                if let Some(password) = password.as_ref().filter(|_| prompted && !self.force) {
+                    self.strength.enforce(password, true)?;
+                }
+                password
+            };
+            self.vault_info.set_password(password);
+            for (key, kind) in &self.field_kinds {
+                self.vault_info.set_field_kind(key, *kind);
+            }
+            self.vault_info.custom_fields =
+                utils::prompt_custom(self.custom_fields, |key| self.vault_info.field_kind(key))?
+                    .into_iter()
+                    .collect();
+            self.vault_info.check_custom_fields()?;
+            self.item.apply(&mut self.vault_info.item)?;
             vault_manager.add_vault(self.vault_info);
             vault_manager.try_export()?;
         }
@@ -89,6 +195,20 @@ impl LprsCommand for Add {
             return Err(LprsError::Other("You can't add empty vault".to_owned()));
         }
 
+        self.strength.validate()?;
+        self.item.validate()?;
+        if let Some(Some(ref password)) = self.password {
+            if !self.force {
+                self.strength.enforce(password, false)?;
+            }
+        }
+        if self.generate.is_none() && !self.interactive && self.generated.is_used() {
+            return Err(LprsError::ArgsConflict(
+                "The `--wordlist`, `--print` and `--clip` options need the `--generate` or \
+                 `--interactive` option"
+                    .to_owned(),
+            ));
+        }
         if let Some(duplicated_key) = utils::get_duplicated_field(&self.custom_fields) {
             if !self.force {
                 return Err(LprsError::Other(format!(
@@ -100,7 +220,7 @@ impl LprsCommand for Add {
             .password
             .as_ref()
             .is_some_and(|p| p.as_ref().is_some_and(String::is_empty))
-            || self.vault_info.name.is_empty()
+            || (self.vault_info.name.is_empty() && !self.interactive)
             || self
                 .vault_info
                 .username
@@ -126,6 +246,32 @@ impl LprsCommand for Add {
         {
             return Err(LprsError::ReservedPrefix(crate::RESERVED_FIELD_PREFIX));
         }
+        if let Some((key, _)) = self
+            .field_kinds
+            .iter()
+            .find(|(key, _)| !self.custom_fields.iter().any(|(k, _)| k == key))
+        {
+            return Err(LprsError::ArgsConflict(format!(
+                "There is no `{key}` custom field to set its kind, add it with `-c {key}`"
+            )));
+        }
+        if let Some(ref qr_path) = self.totp_qr {
+            if !qr_path.is_file() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("file `{}` not found", qr_path.display()),
+                )));
+            }
+        }
+        // Not only the regular files, e.g. `<(command)`
+        if let Some(ref note_path) = self.note_file {
+            if !note_path.exists() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("file `{}` not found", note_path.display()),
+                )));
+            }
+        }
 
         Ok(())
     }
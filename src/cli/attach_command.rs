@@ -0,0 +1,312 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    fs,
+    io::{self, Error as IoError, ErrorKind as IoErrorKind, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    path::PathBuf,
+};
+
+use clap::{Args, Subcommand};
+use either::Either;
+use serde::Serialize;
+
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    clap_parsers::either_parser,
+    impl_commands,
+    utils,
+    vault::{
+        Vaults,
+        attachment::{self, Attachment, HumanSize},
+    },
+};
+
+/// The attach subcommands
+#[derive(Debug, Subcommand)]
+pub enum AttachCommands {
+    /// Attach a file to the vault, or replace an existing attachment
+    Add(Add),
+    /// List the vault attachments [alias `ls`]
+    #[command(alias = "ls")]
+    List(List),
+    /// Get the attachment content, to the stdout or a file
+    Get(Get),
+    /// Remove an attachment from the vault [alias `rm`]
+    #[command(alias = "rm")]
+    Remove(Remove),
+}
+
+impl_commands!(AttachCommands, Add List Get Remove);
+
+#[derive(Debug, Args)]
+/// Attach command, used to manage the vaults attachments
+pub struct Attach {
+    /// The attach command
+    #[command(subcommand)]
+    command: AttachCommands,
+}
+
+#[derive(Debug, Args)]
+/// Attach a file to the vault
+pub struct Add {
+    /// The vault, index or name
+    #[arg(name = "INDEX-or-NAME", value_parser = either_parser::<NonZeroUsize, String>)]
+    location: Either<NonZeroUsize, String>,
+    /// The file to attach. Use `-` to read it from the stdin
+    path:     PathBuf,
+    /// The attachment name, the file name by default. Required when reading
+    /// from the stdin
+    #[arg(short, long)]
+    name:     Option<String>,
+    /// The attachment MIME type, guessed from its name by default
+    #[arg(long)]
+    mime:     Option<String>,
+    /// Replace the attachment if there is one with the same name
+    #[arg(short, long)]
+    force:    bool,
+}
+
+#[derive(Debug, Args)]
+/// List the vault attachments
+pub struct List {
+    /// The vault, index or name
+    #[arg(name = "INDEX-or-NAME", value_parser = either_parser::<NonZeroUsize, String>)]
+    location: Either<NonZeroUsize, String>,
+    /// Returns the output as `json`
+    #[arg(long)]
+    json:     bool,
+}
+
+#[derive(Debug, Args)]
+/// Get the attachment content
+pub struct Get {
+    /// The vault, index or name
+    #[arg(name = "INDEX-or-NAME", value_parser = either_parser::<NonZeroUsize, String>)]
+    location: Either<NonZeroUsize, String>,
+    /// The attachment name
+    name:     String,
+    /// Write the attachment to the given path instead of the stdout
+    #[arg(short, long, value_name = "PATH")]
+    output:   Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+/// Remove an attachment from the vault
+pub struct Remove {
+    /// The vault, index or name
+    #[arg(name = "INDEX-or-NAME", value_parser = either_parser::<NonZeroUsize, String>)]
+    location: Either<NonZeroUsize, String>,
+    /// The attachment name
+    name:     String,
+}
+
+/// The listed attachment, without its content
+#[derive(Serialize)]
+struct ListedAttachment<'a> {
+    /// The attachment name
+    name: &'a str,
+    /// The attachment MIME type
+    mime: &'a str,
+    /// The attachment size in bytes
+    size: usize,
+}
+
+/// Returns the attachment of the vault by its name
+fn find_attachment<'a>(
+    vault_name: &str,
+    attachments: &'a [Attachment],
+    name: &str,
+) -> LprsResult<(usize, &'a Attachment)> {
+    attachments
+        .iter()
+        .enumerate()
+        .find(|(_, a)| a.name == name)
+        .ok_or_else(|| {
+            LprsError::Other(format!(
+                "There is no attachment named `{name}` in the \"{vault_name}\" vault"
+            ))
+        })
+}
+
+impl LprsCommand for Attach {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        self.command.run(vault_manager)
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        self.command.validate_args()
+    }
+}
+
+impl LprsCommand for Add {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        let (_, vault) = utils::vault_by_index_or_name(&self.location, &mut vault_manager.vaults)?;
+        let name = match self.name {
+            Some(name) => name,
+            None => {
+                self.path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .ok_or_else(|| {
+                        LprsError::Other(format!(
+                            "Can't get the file name of `{}`, use `--name`",
+                            self.path.display()
+                        ))
+                    })?
+            }
+        };
+
+        // Read one byte more than the limit, to know if it's larger than it
+        let mut data = Vec::new();
+        let limit = u64::try_from(attachment::MAX_ATTACHMENT_SIZE + 1).unwrap_or(u64::MAX);
+        if self.path.as_os_str() == "-" {
+            io::stdin().take(limit).read_to_end(&mut data)?;
+        } else {
+            fs::File::open(&self.path)?
+                .take(limit)
+                .read_to_end(&mut data)?;
+        }
+        let new_attachment = Attachment::new(name, self.mime, data)?;
+
+        if let Ok((idx, _)) = find_attachment(&vault.name, &vault.attachments, &new_attachment.name)
+        {
+            if !self.force {
+                return Err(LprsError::Other(format!(
+                    "The \"{}\" vault already has an attachment named `{}`, use `--force` to \
+                     replace it",
+                    vault.name, new_attachment.name
+                )));
+            }
+            log::info!("Replacing the `{}` attachment", new_attachment.name);
+            vault.attachments[idx] = new_attachment;
+        } else {
+            vault.attachments.push(new_attachment);
+        }
+        attachment::check_vault(vault)?;
+
+        vault_manager.try_export()
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        if self.path.as_os_str() == "-" {
+            if self.name.is_none() {
+                return Err(LprsError::Other(
+                    "The attachment name is required when reading from the stdin, use `--name`"
+                        .to_owned(),
+                ));
+            }
+            return Ok(());
+        }
+        if !self.path.is_file() {
+            return Err(LprsError::Io(IoError::new(
+                IoErrorKind::NotFound,
+                format!("file `{}` not found", self.path.display()),
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for List {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        let (_, vault) = utils::vault_by_index_or_name(&self.location, &mut vault_manager.vaults)?;
+        if self.json {
+            print!(
+                "{}",
+                serde_json::to_string(
+                    &vault
+                        .attachments
+                        .iter()
+                        .map(|a| {
+                            ListedAttachment {
+                                name: &a.name,
+                                mime: &a.mime,
+                                size: a.data.len(),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                )?
+            );
+            return Ok(());
+        }
+        if vault.attachments.is_empty() {
+            return Err(LprsError::Other(format!(
+                "The \"{}\" vault has no attachments",
+                vault.name
+            )));
+        }
+
+        for attachment in &vault.attachments {
+            println!(
+                "{} ({}, {})",
+                attachment.name,
+                attachment.mime,
+                HumanSize(attachment.data.len())
+            );
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Get {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        let (_, vault) = utils::vault_by_index_or_name(&self.location, &mut vault_manager.vaults)?;
+        let (_, attachment) = find_attachment(&vault.name, &vault.attachments, &self.name)?;
+
+        if let Some(path) = self.output.filter(|p| p.as_os_str() != "-") {
+            let mut file = utils::create_private_file(&path)?;
+            file.write_all(&attachment.data)?;
+            return file.flush().map_err(LprsError::from);
+        }
+
+        let mut stdout = io::stdout();
+        if stdout.is_terminal() && std::str::from_utf8(&attachment.data).is_err() {
+            return Err(LprsError::Other(format!(
+                "The `{}` attachment is binary, use `--output` to write it to a file",
+                attachment.name
+            )));
+        }
+        stdout.write_all(&attachment.data)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        if let Some(path) = self.output.as_ref().filter(|p| p.as_os_str() != "-") {
+            if path.exists() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::AlreadyExists,
+                    format!("file `{}` is already exists", path.display()),
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Remove {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        let (_, vault) = utils::vault_by_index_or_name(&self.location, &mut vault_manager.vaults)?;
+        let (idx, _) = find_attachment(&vault.name, &vault.attachments, &self.name)?;
+        vault.attachments.remove(idx);
+
+        vault_manager.try_export()
+    }
+}
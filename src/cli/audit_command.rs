@@ -0,0 +1,123 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::PathBuf,
+};
+
+use clap::Args;
+
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    vault::{
+        Vaults,
+        audit::{self, AuditOptions},
+        breach::BreachDb,
+    },
+};
+
+#[derive(Debug, Args)]
+/// Audit command, used to find the weak, reused and old passwords
+pub struct Audit {
+    /// The minimum password score, from 0 to 100. The passwords with a lower
+    /// score are weak
+    #[arg(long, value_name = "SCORE", default_value_t = 80.0)]
+    min_score:     f64,
+    /// The maximum password age in days, the older passwords are reported
+    #[arg(long, value_name = "DAYS", default_value_t = 365)]
+    max_age:       u64,
+    /// A file of more service domains that support TOTP, a domain per line
+    #[arg(long, value_name = "PATH")]
+    totp_services: Option<PathBuf>,
+    /// A local HaveIBeenPwned passwords file, ordered by hash, SHA-1 or NTLM.
+    /// The passwords found in it are reported
+    #[arg(long, value_name = "PATH")]
+    breach_db:     Option<PathBuf>,
+    /// Returns the output as `json`
+    #[arg(long)]
+    json:          bool,
+}
+
+impl Audit {
+    /// Returns the domains of the services that support TOTP, the well known
+    /// ones and the ones in the given file
+    fn totp_services(&self) -> LprsResult<Vec<String>> {
+        let mut services: Vec<String> = audit::TOTP_SERVICES
+            .iter()
+            .map(|domain| (*domain).to_owned())
+            .collect();
+        if let Some(ref path) = self.totp_services {
+            services.extend(
+                fs::read_to_string(path)?
+                    .lines()
+                    .map(|line| line.trim().trim_end_matches('.').to_lowercase())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#')),
+            );
+        }
+        Ok(services)
+    }
+}
+
+impl LprsCommand for Audit {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        let options = AuditOptions {
+            min_score:     self.min_score,
+            max_age:       self.max_age,
+            totp_services: self.totp_services()?,
+            breach_db:     self.breach_db.as_deref().map(BreachDb::open).transpose()?,
+        };
+        let report = audit::audit(&vault_manager.vaults, &options)?;
+        let findings = report.findings_count();
+
+        if self.json {
+            print!("{}", serde_json::to_string(&report)?);
+        } else if findings == 0 {
+            println!("No findings in {} vaults", report.vaults.len());
+        } else {
+            println!("{report}");
+        }
+
+        if findings != 0 {
+            return Err(LprsError::Audit(format!(
+                "{findings} findings in {} of {} vaults",
+                report.vaults_with_findings().count(),
+                report.vaults.len()
+            )));
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        if !(0.0..=100.0).contains(&self.min_score) {
+            return Err(LprsError::Other(
+                "The minimum score must be between 0 and 100".to_owned(),
+            ));
+        }
+        for path in [&self.totp_services, &self.breach_db].into_iter().flatten() {
+            if !path.is_file() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("file `{}` not found", path.display()),
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use clap::{Args, CommandFactory, Subcommand};
+
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    clap_parsers::config_key_parser,
+    cli::Cli,
+    config::{self, CommandsDefaults, global::GlobalConfig},
+    impl_commands,
+    vault::Vaults,
+};
+
+/// The config subcommands
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommands {
+    /// List the config values [alias `ls`]
+    #[command(alias = "ls")]
+    List(List),
+    /// Get a config value
+    Get(Get),
+    /// Set a config value
+    Set(Set),
+    /// Unset a config value
+    Unset(Unset),
+}
+
+impl_commands!(ConfigCommands, List Get Set Unset);
+
+#[derive(Debug, Args)]
+/// Config command, used to manage the global config
+pub struct Config {
+    /// The config command
+    #[command(subcommand)]
+    command: ConfigCommands,
+}
+
+#[derive(Debug, Args)]
+/// List the config values
+pub struct List {
+    /// Returns the output as `json`
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+/// Get a config value
+pub struct Get {
+    /// The config key, `COMMAND.ARGUMENT`
+    #[arg(value_name = "COMMAND.ARGUMENT", value_parser = config_key_parser)]
+    key: (String, String),
+}
+
+#[derive(Debug, Args)]
+/// Set a config value
+pub struct Set {
+    /// The config key, `COMMAND.ARGUMENT`
+    #[arg(value_name = "COMMAND.ARGUMENT", value_parser = config_key_parser)]
+    key:   (String, String),
+    /// The default value of the argument, a TOML value (e.g. `true`, `20` or
+    /// `["a", "b"]`) or a string
+    value: String,
+}
+
+#[derive(Debug, Args)]
+/// Unset a config value
+pub struct Unset {
+    /// The config key, `COMMAND.ARGUMENT`
+    #[arg(value_name = "COMMAND.ARGUMENT", value_parser = config_key_parser)]
+    key: (String, String),
+}
+
+impl LprsCommand for Config {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        self.command.run(vault_manager)
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        self.command.validate_args()
+    }
+}
+
+impl LprsCommand for List {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let global_config = GlobalConfig::load()?;
+        if self.json {
+            print!("{}", serde_json::to_string(&global_config)?);
+            return Ok(());
+        }
+
+        for (command, args) in &global_config.defaults {
+            for (arg, value) in args {
+                println!("{command}.{arg} = {value}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Get {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let (command, argument) = &self.key;
+        let value = GlobalConfig::load()?
+            .get(command, argument)
+            .cloned()
+            .ok_or_else(|| {
+                LprsError::Config(format!("There is no `{command}.{argument}` value"))
+            })?;
+        match value {
+            toml::Value::String(value) => println!("{value}"),
+            value => println!("{value}"),
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Set {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let (command, argument) = self.key;
+        let value = config::parse_value(&self.value);
+
+        let mut defaults = CommandsDefaults::new();
+        defaults
+            .entry(command.clone())
+            .or_default()
+            .insert(argument.clone(), value.clone());
+        config::check_defaults(&defaults)?;
+        config::apply_defaults(Cli::command(), &defaults)?;
+
+        let mut global_config = GlobalConfig::load()?;
+        global_config.set(command, argument, value);
+        global_config.save()
+    }
+}
+
+impl LprsCommand for Unset {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let (command, argument) = &self.key;
+        let mut global_config = GlobalConfig::load()?;
+        if global_config.unset(command, argument).is_none() {
+            return Err(LprsError::Config(format!(
+                "There is no `{command}.{argument}` value"
+            )));
+        }
+        global_config.save()
+    }
+}
@@ -0,0 +1,112 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::PathBuf,
+};
+
+use clap::Args;
+use sha2::Digest;
+
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    utils,
+    vault::{Vault, Vaults, backup, diff, plaintext},
+};
+
+#[derive(Debug, Args)]
+/// Diff command, used to compare the vaults with an export or another vaults
+/// file
+pub struct Diff {
+    /// The `lprs` export or the vaults file to compare with
+    path:         PathBuf,
+    /// Decryption password of the other vaults, if there is not, will use the
+    /// master password
+    #[arg(short = 'p', long)]
+    #[allow(clippy::option_option)]
+    password:     Option<Option<String>>,
+    /// Show the secrets (passwords, TOTP secrets, secret custom fields and the
+    /// items secrets) instead of masking them
+    #[arg(long)]
+    show_secrets: bool,
+    /// Returns the output as `json`
+    #[arg(long)]
+    json:         bool,
+}
+
+impl Diff {
+    /// Load the other vaults, it can be an encrypted backup, a plaintext
+    /// export, a `lprs-json` export or a vaults file
+    fn other_vaults(&self, master_password: [u8; 32]) -> LprsResult<Vec<Vault>> {
+        let data = fs::read(&self.path)?;
+        if plaintext::is_plaintext(&data) {
+            return plaintext::import(&data);
+        }
+
+        let decryption_key: [u8; 32] =
+            utils::user_secret(self.password.clone(), "Decryption password:", false)?
+                .map_or(master_password, |p| sha2::Sha256::digest(p).into());
+        if backup::is_backup(&data) {
+            backup::import(&data, &decryption_key)
+        } else if data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[') {
+            Vaults::json_reload(&decryption_key, &data, false).map(|(vaults, _)| vaults)
+        } else {
+            Vaults::try_reload(self.path.clone(), decryption_key).map(|v| v.vaults)
+        }
+    }
+}
+
+impl LprsCommand for Diff {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        log::debug!(
+            "Comparing the vaults file `{}` with `{}`",
+            vault_manager.vaults_file.display(),
+            self.path.display()
+        );
+
+        let other_vaults = self.other_vaults(vault_manager.master_password)?;
+        let vaults_diff = diff::diff(&vault_manager.vaults, &other_vaults, self.show_secrets);
+
+        if self.json {
+            print!("{}", serde_json::to_string(&vaults_diff)?);
+        } else if vaults_diff.is_empty() {
+            println!("There are no differences");
+        } else {
+            println!("{vaults_diff}");
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        if !self.path.exists() {
+            return Err(LprsError::Io(IoError::new(
+                IoErrorKind::NotFound,
+                format!("file `{}` not found", self.path.display()),
+            )));
+        }
+        if self.path.is_dir() {
+            return Err(LprsError::Io(IoError::new(
+                IoErrorKind::InvalidInput,
+                format!("file `{}` is a directory", self.path.display()),
+            )));
+        }
+        Ok(())
+    }
+}
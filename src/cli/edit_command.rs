@@ -14,7 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
 
-use std::num::NonZeroUsize;
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    num::NonZeroUsize,
+    path::PathBuf,
+};
 
 use clap::Args;
 use either::Either;
@@ -23,9 +27,12 @@ use crate::{
     LprsCommand,
     LprsError,
     LprsResult,
-    clap_parsers::{either_parser, kv_parser},
+    clap_parsers::{either_parser, field_kind_parser, kv_parser},
+    cli::{editor, gen_command::GeneratedPassword, wizard::VaultWizard},
+    config::policies::PoliciesConfig,
+    generator::GeneratorSpec,
     utils,
-    vault::{Vaults, cipher},
+    vault::{Vaults, cipher, custom_field::FieldKind, item::ItemArgs, strength::StrengthPolicy},
 };
 
 #[derive(Debug, Args)]
@@ -47,12 +54,34 @@ pub struct Edit {
     /// If there is no value for it you will prompt it
     #[allow(clippy::option_option)]
     password:      Option<Option<String>>,
+    /// Generate a new password, with the generator spec `LENGTH[:CLASSES]`,
+    /// `words:N[:SEPARATOR]` or `pattern:TEMPLATE`. By default 18 characters of
+    /// all the classes
+    #[arg(
+        short = 'g',
+        long,
+        value_name = "SPEC",
+        require_equals = true,
+        conflicts_with = "password"
+    )]
+    #[allow(clippy::option_option)]
+    regenerate:    Option<Option<GeneratorSpec>>,
+    #[command(flatten)]
+    generated:     GeneratedPassword,
     #[arg(short, long)]
     /// The new vault service, make it empty string to delete it
     service:       Option<String>,
     #[arg(short = 'o', long)]
-    /// The new vault note, make it empty string to delete it
+    /// The new vault note, make it empty string to delete it, `-` to read it
+    /// from the stdin
     note:          Option<String>,
+    /// Read the new vault note from the given file as it is, e.g. a
+    /// multi-line note
+    #[arg(long, value_name = "PATH", conflicts_with = "note")]
+    note_file:     Option<PathBuf>,
+    #[arg(long)]
+    /// The new vault folder, make it empty string to delete it
+    folder:        Option<String>,
     /// The TOTP secret, make it empty string to delete it
     ///
     /// If there is no value you will prompt it
@@ -66,11 +95,36 @@ pub struct Edit {
     #[arg(name = "KEY=VALUE", short = 'c', long = "custom")]
     #[arg(value_parser = kv_parser)]
     custom_fields: Vec<(String, Option<String>)>,
+    /// Set the kind of a custom field, `text`, `secret`, `url`, `email`,
+    /// `totp` or `multiline`
+    ///
+    /// The field value must be valid for its new kind
+    #[arg(long = "kind", value_name = "KEY=KIND")]
+    #[arg(value_parser = field_kind_parser)]
+    field_kinds:   Vec<(String, FieldKind)>,
+    #[command(flatten)]
+    item:          ItemArgs,
+    #[command(flatten)]
+    strength:      StrengthPolicy,
     /// Force edit, will not return error if there is a problem with the args.
     ///
-    /// For example, duplication in the custom fields and try to editing nothing
+    /// For example, duplication in the custom fields, try to editing nothing
+    /// and a weak password
     #[arg(short, long)]
     force:         bool,
+    /// Walk through the vault fields with prompts, the current values are the
+    /// initial values
+    #[arg(short, long, conflicts_with_all = [
+        "name", "username", "password", "regenerate", "service", "note", "note_file", "folder",
+        "totp_secret", "KEY=VALUE", "field_kinds", "ItemArgs",
+    ])]
+    interactive:   bool,
+    /// Edit the vault as TOML in your editor, `$VISUAL` or `$EDITOR`
+    #[arg(long, conflicts_with_all = [
+        "name", "username", "password", "regenerate", "service", "note", "note_file", "folder",
+        "totp_secret", "KEY=VALUE", "field_kinds", "ItemArgs", "interactive",
+    ])]
+    editor:        bool,
 }
 
 impl LprsCommand for Edit {
@@ -85,15 +139,38 @@ impl LprsCommand for Edit {
             }
         };
 
+        if self.interactive {
+            VaultWizard {
+                generated: &self.generated,
+                strength:  &self.strength,
+                force:     self.force,
+            }
+            .run(vault)?;
+            return vault_manager.try_export();
+        }
+        if self.editor {
+            if !editor::edit_vault(vault, &self.strength, self.force)? {
+                log::info!("The vault is not changed");
+                return Ok(());
+            }
+            return vault_manager.try_export();
+        }
+
         log::info!("Applying the new values to the vault");
         if let Some(new_name) = self.name {
             vault.name = new_name;
         }
         if let Some(ref new_password) = self.password {
             if new_password.as_deref().is_some_and(|s| s.is_empty()) {
-                vault.password = None;
+                vault.set_password(None);
             } else {
-                vault.password = utils::user_secret(self.password, "New vault password:", false)?;
+                let prompted = new_password.is_none();
+                let new_password =
+                    utils::user_secret(self.password.clone(), "New vault password:", false)?;
+                if let Some(password) = new_password.as_ref().filter(|_| !self.force) {
+                    self.strength.enforce(password, prompted)?;
+                }
+                vault.set_password(new_password);
             }
         }
         if let Some(totp_secret) = utils::user_secret(self.totp_secret, "TOTP Secret:", false)? {
@@ -120,35 +197,75 @@ impl LprsCommand for Edit {
                 vault.service = Some(new_service);
             }
         }
-        if let Some(new_note) = self.note {
+        if let Some(new_note) = utils::read_note(self.note, self.note_file.as_deref())? {
             if new_note.is_empty() {
                 vault.note = None;
             } else {
                 vault.note = Some(new_note);
             }
         }
-        utils::apply_custom_fields(
-            &mut vault.custom_fields,
-            utils::prompt_custom(self.custom_fields)?,
-        );
+        if let Some(new_folder) = self.folder {
+            if new_folder.is_empty() {
+                vault.folder = None;
+            } else {
+                vault.folder = Some(new_folder);
+            }
+        }
+        if let Some(spec) = self.regenerate {
+            let mut spec = spec.unwrap_or_default();
+            if let Some(policy) = PoliciesConfig::load()?.vault_policy(vault) {
+                spec = spec.with_policy(policy.clone())?;
+            }
+            vault.set_password(Some(self.generated.generate(&spec)?));
+        }
+        for (key, kind) in &self.field_kinds {
+            if !vault.custom_fields.contains_key(key)
+                && !self.custom_fields.iter().any(|(k, _)| k == key)
+            {
+                return Err(LprsError::InvalidCustomField(format!(
+                    "There is no `{key}` custom field to set its kind, add it with `-c {key}`"
+                )));
+            }
+            vault.set_field_kind(key, *kind);
+        }
+        let custom_fields = utils::prompt_custom(self.custom_fields, |key| vault.field_kind(key))?;
+        utils::apply_custom_fields(vault, custom_fields);
+        vault.check_custom_fields()?;
+        self.item.apply(&mut vault.item)?;
 
         vault_manager.try_export()
     }
 
     fn validate_args(&self) -> LprsResult<()> {
         if !self.force
+            && !self.interactive
+            && !self.editor
             && self.name.is_none()
             && self.username.is_none()
             && self.password.is_none()
+            && self.regenerate.is_none()
             && self.service.is_none()
             && self.note.is_none()
+            && self.note_file.is_none()
+            && self.folder.is_none()
             && self.totp_secret.is_none()
             && self.custom_fields.is_empty()
+            && self.field_kinds.is_empty()
+            && self.item.is_empty()
         {
             return Err(LprsError::Other(
                 "You must edit one option at least".to_owned(),
             ));
         }
+        self.strength.validate()?;
+        self.item.validate()?;
+        if self.regenerate.is_none() && !self.interactive && self.generated.is_used() {
+            return Err(LprsError::ArgsConflict(
+                "The `--wordlist`, `--print` and `--clip` options need the `--regenerate` or \
+                 `--interactive` option"
+                    .to_owned(),
+            ));
+        }
         if let Some(duplicated_key) = utils::get_duplicated_field(&self.custom_fields) {
             if !self.force {
                 return Err(LprsError::Other(format!(
@@ -163,6 +280,15 @@ impl LprsCommand for Edit {
         {
             return Err(LprsError::ReservedPrefix(crate::RESERVED_FIELD_PREFIX));
         }
+        // Not only the regular files, e.g. `<(command)`
+        if let Some(ref note_path) = self.note_file {
+            if !note_path.exists() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("file `{}` not found", note_path.display()),
+                )));
+            }
+        }
 
         Ok(())
     }
@@ -0,0 +1,347 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, DirBuilder, File, OpenOptions},
+    io::{self, IsTerminal, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use inquire::Confirm;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    LprsError,
+    LprsResult,
+    utils,
+    vault::{self, Vault, cipher, custom_field::FieldKind, item::Item, strength::StrengthPolicy},
+};
+
+/// The header of the edited file, the TOML comments are ignored
+const HEADER: &str = "# Edit the vault, then save the file and close the editor.\n# Remove a \
+                      field or make it empty to delete it, the unknown keys are rejected.\n\n";
+
+/// The vault fields that can be edited in the editor
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+struct EditableVault {
+    /// The name of the vault
+    name:               String,
+    /// The username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    username:           Option<String>,
+    /// The password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password:           Option<String>,
+    /// The service name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    service:            Option<String>,
+    /// The note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note:               Option<String>,
+    /// The folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder:             Option<String>,
+    /// The TOTP secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    totp_secret:        Option<String>,
+    /// The TOTP hash function
+    #[serde(default)]
+    totp_hash:          cipher::TotpHash,
+    /// The TOTP code digits
+    #[serde(default = "vault::default_totp_digits")]
+    totp_digits:        u8,
+    /// The custom fields
+    #[serde(default)]
+    custom_fields:      BTreeMap<String, String>,
+    /// The kinds of the custom fields, the fields without a kind are secrets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    custom_field_kinds: BTreeMap<String, FieldKind>,
+    /// The item type with its fields
+    #[serde(default, skip_serializing_if = "Item::is_login")]
+    item:               Item,
+}
+
+/// A temporary file in a private directory, overwritten with zeros then
+/// removed with its directory when it's dropped
+struct SecretFile {
+    /// The private directory of the file
+    dir:  PathBuf,
+    /// The file path
+    path: PathBuf,
+}
+
+impl EditableVault {
+    /// Returns the editable fields of the vault
+    fn new(vault: &Vault) -> Self {
+        Self {
+            name:               vault.name.clone(),
+            username:           vault.username.clone(),
+            password:           vault.password.clone(),
+            service:            vault.service.clone(),
+            note:               vault.note.clone(),
+            folder:             vault.folder.clone(),
+            totp_secret:        vault.totp_secret.clone(),
+            totp_hash:          vault.totp_hash.clone(),
+            totp_digits:        vault.totp_digits,
+            custom_fields:      vault.custom_fields.clone(),
+            custom_field_kinds: vault.custom_field_kinds.clone(),
+            item:               vault.item.clone(),
+        }
+    }
+
+    /// Parse the edited TOML, then validate the fields
+    fn parse(content: &str) -> LprsResult<Self> {
+        let mut edited: Self =
+            toml::from_str(content).map_err(|err| LprsError::Editor(err.message().to_owned()))?;
+        edited.name = edited.name.trim().to_string();
+        if edited.name.is_empty() {
+            return Err(LprsError::Editor(
+                "The vault name can't be empty".to_owned(),
+            ));
+        }
+        if let Some(secret) = edited.totp_secret.as_ref().filter(|s| !s.is_empty()) {
+            cipher::base32_decode(secret).map_err(|_| {
+                LprsError::Base32("Invalid TOTP secret, must be valid base32 string".to_owned())
+            })?;
+        }
+        if !(6..=8).contains(&edited.totp_digits) {
+            return Err(LprsError::Editor(
+                "The TOTP digits must be between 6 and 8".to_owned(),
+            ));
+        }
+        if edited
+            .custom_fields
+            .keys()
+            .any(|k| k.starts_with(crate::RESERVED_FIELD_PREFIX))
+        {
+            return Err(LprsError::ReservedPrefix(crate::RESERVED_FIELD_PREFIX));
+        }
+        if edited.custom_fields.keys().any(String::is_empty) {
+            return Err(LprsError::EmptyValue);
+        }
+        for (key, kind) in &edited.custom_field_kinds {
+            let Some(value) = edited.custom_fields.get(key).filter(|v| !v.is_empty()) else {
+                return Err(LprsError::Editor(format!(
+                    "There is no `{key}` custom field to set its kind"
+                )));
+            };
+            kind.check(key, value)?;
+        }
+        edited.item = edited.item.normalized()?;
+        Ok(edited)
+    }
+
+    /// Apply the edited fields to the vault, the empty values remove the
+    /// fields
+    fn apply(self, vault: &mut Vault) {
+        let non_empty = |value: Option<String>| value.filter(|v| !v.is_empty());
+
+        vault.name = self.name;
+        vault.username = non_empty(self.username);
+        vault.set_password(non_empty(self.password));
+        vault.service = non_empty(self.service);
+        vault.note = non_empty(self.note);
+        vault.folder = non_empty(self.folder);
+        vault.totp_secret = non_empty(self.totp_secret);
+        vault.totp_hash = self.totp_hash;
+        vault.totp_digits = self.totp_digits;
+        vault.item = self.item;
+
+        let removed_fields = vault
+            .custom_fields
+            .keys()
+            .filter(|key| !self.custom_fields.contains_key(*key))
+            .map(|key| (key.clone(), String::new()))
+            .collect::<Vec<_>>();
+        utils::apply_custom_fields(
+            vault,
+            removed_fields
+                .into_iter()
+                .chain(
+                    self.custom_fields
+                        .into_iter()
+                        .filter(|(_, v)| !v.is_empty()),
+                )
+                .collect(),
+        );
+        vault.custom_field_kinds.clear();
+        for (key, kind) in self.custom_field_kinds {
+            vault.set_field_kind(&key, kind);
+        }
+    }
+}
+
+impl SecretFile {
+    /// Create a new private file (readable and writable only by the user) in
+    /// a private directory, in a memory filesystem when there is one. The
+    /// directory keeps the file private even if the editor replaced it
+    fn create(file_name: &str) -> LprsResult<(Self, File)> {
+        let dir = secret_dir().join(format!(
+            "lprs-{}-{:016x}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        log::debug!("Creating the secret directory: {}", dir.display());
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&dir)?;
+
+        let path = dir.join(file_name);
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let secret_file = Self { dir, path };
+        let file = options.open(&secret_file.path)?;
+        Ok((secret_file, file))
+    }
+}
+
+impl Drop for SecretFile {
+    fn drop(&mut self) {
+        log::debug!("Removing the secret file: {}", self.path.display());
+        // The editor may have replaced the file, so the current one is
+        // overwritten
+        if let Ok(mut file) = OpenOptions::new().write(true).open(&self.path) {
+            if let Ok(metadata) = file.metadata() {
+                let zeros = vec![0; usize::try_from(metadata.len()).unwrap_or_default()];
+                let _ = file
+                    .seek(SeekFrom::Start(0))
+                    .and_then(|_| file.write_all(&zeros))
+                    .and_then(|()| file.sync_all());
+            }
+        }
+        // Removes the file with the editor leftovers, e.g. its backup file
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            log::error!("Can't remove `{}`: {err}", self.dir.display());
+        }
+    }
+}
+
+/// Returns the directory of the secret files, a memory filesystem (tmpfs)
+/// when there is one, otherwise the temporary directory
+fn secret_dir() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain([PathBuf::from("/dev/shm")])
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(env::temp_dir)
+}
+
+/// Returns the user editor, `$VISUAL` or `$EDITOR`
+fn editor() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_owned()
+            } else {
+                "vi".to_owned()
+            }
+        })
+}
+
+/// Open the file in the user editor, and wait for it
+fn open_editor(path: &Path) -> LprsResult<()> {
+    let editor = editor();
+    let mut args = editor.split_whitespace();
+    let program = args.next().expect("The editor is not empty");
+    log::info!("Opening the editor: {editor}");
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|err| LprsError::Editor(format!("Can't open the editor `{editor}`: {err}")))?;
+    if !status.success() {
+        return Err(LprsError::Editor(format!(
+            "The editor `{editor}` exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+/// Edit the vault in the user editor as TOML, then apply the changes to it.
+/// Returns `false` if nothing is changed.
+///
+/// ## Errors
+/// - If the secret file can't be created or read
+/// - If the editor can't be opened or it failed
+/// - If the edited vault is invalid, and the user doesn't want to edit it again
+/// - If the new password is weak, and the user refused it
+pub fn edit_vault(vault: &mut Vault, strength: &StrengthPolicy, force: bool) -> LprsResult<bool> {
+    let current = EditableVault::new(vault);
+    let content = format!(
+        "{HEADER}{}",
+        toml::to_string_pretty(&current).map_err(|err| LprsError::Editor(err.to_string()))?
+    );
+    let (secret_file, mut file) = SecretFile::create("vault.toml")?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    let edited = loop {
+        open_editor(&secret_file.path)?;
+        let result = fs::read_to_string(&secret_file.path)
+            .map_err(LprsError::from)
+            .and_then(|content| EditableVault::parse(&content))
+            .and_then(|edited| {
+                if let Some(password) = edited
+                    .password
+                    .as_ref()
+                    .filter(|p| !force && !p.is_empty() && edited.password != current.password)
+                {
+                    strength.enforce(password, true)?;
+                }
+                Ok(edited)
+            });
+        match result {
+            Ok(edited) => break edited,
+            Err(LprsError::Inquire(err)) => return Err(LprsError::Inquire(err)),
+            Err(err) if !io::stdin().is_terminal() => return Err(err),
+            Err(err) => {
+                eprintln!("{err}");
+                if !Confirm::new("Edit the vault again?")
+                    .with_default(true)
+                    .prompt()?
+                {
+                    return Err(err);
+                }
+            }
+        }
+    };
+    drop(secret_file);
+
+    if edited == current {
+        return Ok(false);
+    }
+    edited.apply(vault);
+    Ok(true)
+}
@@ -15,7 +15,6 @@
 // along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
 
 use std::{
-    fs,
     io::{self, Error as IoError, ErrorKind as IoErrorKind, Write},
     path::PathBuf,
 };
@@ -23,19 +22,69 @@ use std::{
 use clap::Args;
 use sha2::Digest;
 
-use crate::{LprsCommand, LprsError, LprsResult, utils, vault::Vaults};
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    utils,
+    vault::{
+        Format,
+        Vaults,
+        backup,
+        bitwarden::BitWardenVaults,
+        csv::{self, CsvColumn, CsvPreset},
+        kdbx,
+        plaintext,
+    },
+};
 
 #[derive(Debug, Args)]
-/// Export command. The exported file will be a json file.
+/// Export command, used to export the vaults as encrypted backup or to other
+/// formats.
 pub struct Export {
     // TODO: `force` flag to write on existing file
     /// The path to export to. Use `-` to export to the stdout.
     path:                PathBuf,
+    /// The format of the exported file
+    #[arg(short, long, value_enum, default_value_t)]
+    format:              Format,
+    /// Export the vaults as documented plaintext JSON instead of encrypting
+    /// them, for the `lprs` format. Requires `--i-understand`
+    #[arg(
+        long,
+        requires = "i_understand",
+        conflicts_with = "encryption_password"
+    )]
+    plaintext:           bool,
+    /// Confirm that you understand that the plaintext export is NOT encrypted
+    #[arg(long, requires = "plaintext")]
+    i_understand:        bool,
     /// Encryption password of the exported vaults, if there is not, will use
     /// the master password
+    ///
+    /// For the `kdbx` format, it's the database password, you will be prompted
+    /// for it if there is no key file
     #[arg(short = 'p', long)]
     #[allow(clippy::option_option)]
     encryption_password: Option<Option<String>>,
+    /// The key file of the `kdbx` database
+    #[arg(short, long, value_name = "PATH")]
+    key_file:            Option<PathBuf>,
+    /// Export the CSV in the layout of this manager, for the `csv` format
+    #[arg(long, value_enum)]
+    preset:              Option<CsvPreset>,
+    /// The CSV columns, for the `csv` format. e.g. `name,password,service=url`
+    ///
+    /// The fields are name, username, password, service, note, folder and
+    /// totp, other fields are custom fields. By default all the fields are
+    /// exported.
+    #[arg(
+        long,
+        value_name = "FIELD[=COLUMN]",
+        value_delimiter = ',',
+        conflicts_with = "preset"
+    )]
+    columns:             Vec<CsvColumn>,
 }
 
 impl LprsCommand for Export {
@@ -46,44 +95,147 @@ impl LprsCommand for Export {
             self.path.display(),
         );
 
-        let encryption_key: Option<[u8; 32]> =
-            utils::user_secret(self.encryption_password, "Encryption Password:", false)?
-                .map(|p| sha2::Sha256::digest(p).into());
+        let exported_data = match self.format {
+            Format::Lprs if self.plaintext => {
+                plaintext::export(&vault_manager.vaults)?.into_bytes()
+            }
+            Format::Lprs | Format::LprsJson => {
+                let encryption_key: Option<[u8; 32]> =
+                    utils::user_secret(self.encryption_password, "Encryption Password:", false)?
+                        .map(|p| sha2::Sha256::digest(p).into());
+
+                let encryption_key = encryption_key
+                    .as_ref()
+                    .unwrap_or(&vault_manager.master_password);
 
-        let exported_data = vault_manager.json_export(
-            encryption_key
-                .as_ref()
-                .unwrap_or(&vault_manager.master_password),
-        )?;
+                if self.format == Format::Lprs {
+                    backup::export(&vault_manager.vaults, encryption_key)?
+                } else {
+                    vault_manager.json_export(encryption_key)?.into_bytes()
+                }
+            }
+            Format::BitWarden => BitWardenVaults::export(&vault_manager.vaults)?.into_bytes(),
+            Format::Kdbx => {
+                let password = utils::user_secret(
+                    self.encryption_password
+                        .or_else(|| self.key_file.is_none().then_some(None)),
+                    "KDBX password:",
+                    true,
+                )?;
+                kdbx::export(
+                    &vault_manager.vaults,
+                    password.as_deref(),
+                    self.key_file.as_deref(),
+                )?
+            }
+            Format::Csv => csv::export(&vault_manager.vaults, self.preset, &self.columns)?,
+            Format::GoogleAuthMigration => {
+                unreachable!("The format is checked in the `validate_args`")
+            }
+        };
+
+        let attachments: usize = vault_manager
+            .vaults
+            .iter()
+            .map(|v| v.attachments.len())
+            .sum();
+        if attachments != 0 && matches!(self.format, Format::BitWarden | Format::Kdbx | Format::Csv)
+        {
+            eprintln!(
+                "WARNING: {attachments} attachment{s} {are} not exported, the format doesn't \
+                 support them. Use the `lprs` format to export them",
+                s = if attachments >= 2 { "s" } else { "" },
+                are = if attachments >= 2 { "are" } else { "is" },
+            );
+        }
+        let renamed_fields = if self.format == Format::Kdbx {
+            kdbx::renamed_fields(&vault_manager.vaults)
+        } else {
+            0
+        };
+        if renamed_fields != 0 {
+            eprintln!(
+                "WARNING: {renamed_fields} custom field{s} {are} named after a KeePass standard \
+                 field (e.g. `Password`), exported with the `lprs:` prefix so the standard \
+                 field{s} {are} not overwritten",
+                s = if renamed_fields >= 2 { "s" } else { "" },
+                are = if renamed_fields >= 2 { "are" } else { "is" },
+            );
+        }
+        if !self.format.is_encrypted() || self.plaintext {
+            eprintln!(
+                "WARNING: The exported file is NOT encrypted, anyone who can read it can read all \
+                 your passwords and secrets. Keep it safe and delete it once you are done with it"
+            );
+        }
 
         // if the path is not `-` write to it
         if self.path.as_os_str() != "-" {
-            return fs::write(&self.path, exported_data).map_err(LprsError::from);
+            let mut file = utils::create_private_file(&self.path)?;
+            file.write_all(&exported_data)?;
+            return file.flush().map_err(LprsError::from);
         }
 
         // Write in the stdout if the path is `-`
         let mut stdout = io::stdout();
-        stdout.write_all(exported_data.as_bytes())?;
+        stdout.write_all(&exported_data)?;
         stdout.flush()?;
 
         Ok(())
     }
 
     fn validate_args(&self) -> LprsResult<()> {
+        if !self.format.is_exportable() {
+            return Err(LprsError::Other(
+                "The vaults can't be exported in this format, it's import only".to_owned(),
+            ));
+        }
+        if self.encryption_password.is_some() && !self.format.is_encrypted() {
+            return Err(LprsError::ArgsConflict(
+                "The encryption password can only be used with the `lprs`, `lprs-json` and `kdbx` \
+                 formats"
+                    .to_owned(),
+            ));
+        }
+        if let Some(ref key_file) = self.key_file {
+            if self.format != Format::Kdbx {
+                return Err(LprsError::ArgsConflict(
+                    "The key file can only be used with the `kdbx` format".to_owned(),
+                ));
+            }
+            if !key_file.is_file() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("key file `{}` not found", key_file.display()),
+                )));
+            }
+        }
+        if self.plaintext && self.format != Format::Lprs {
+            return Err(LprsError::ArgsConflict(
+                "The plaintext export can only be used with the `lprs` format".to_owned(),
+            ));
+        }
+        if (self.preset.is_some() || !self.columns.is_empty()) && self.format != Format::Csv {
+            return Err(LprsError::ArgsConflict(
+                "The preset and the columns can only be used with the `csv` format".to_owned(),
+            ));
+        }
         // Skip checking the path if we will export to the stdout
         if self.path.as_os_str() == "-" {
             return Ok(());
         }
 
-        if !self
-            .path
-            .extension()
-            .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("json"))
-        {
-            return Err(LprsError::Io(IoError::new(
-                IoErrorKind::InvalidInput,
-                format!("file `{}` is not a json file", self.path.display()),
-            )));
+        if let Some(extension) = self.format.extension() {
+            if !self
+                .path
+                .extension()
+                .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(extension))
+            {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::InvalidInput,
+                    format!("file `{}` is not a {extension} file", self.path.display()),
+                )));
+            }
         }
         if self.path.exists() {
             return Err(LprsError::Io(IoError::new(
@@ -14,17 +14,76 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
 
-use std::num::NonZeroU64;
+use std::{
+    io::{self, Write},
+    num::{NonZeroU64, NonZeroUsize},
+    path::PathBuf,
+};
 
 use clap::Args;
 
-use crate::{LprsCommand, LprsError, LprsResult, vault::Vaults};
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    generator::{
+        CharsGenerator,
+        DEFAULT_LENGTH,
+        GeneratorSpec,
+        passphrase::{Capitalization, PassphraseGenerator, Wordlist},
+        pattern::PatternGenerator,
+        policy::PasswordPolicy,
+    },
+    utils,
+    vault::Vaults,
+};
+
+/// The generated password options of the `add` and `edit` commands
+#[derive(Debug, Args)]
+pub struct GeneratedPassword {
+    /// The wordlist of the `words:N` generator spec, see `lprs gen --wordlist`
+    #[arg(long, value_name = "PATH")]
+    pub wordlist: Option<PathBuf>,
+    /// Print the generated password to the stdout
+    #[arg(long)]
+    pub print:    bool,
+    /// Copy the generated password to the clipboard
+    #[arg(long)]
+    pub clip:     bool,
+}
+
+impl GeneratedPassword {
+    /// Returns true if one of the options is used
+    pub fn is_used(&self) -> bool {
+        self.wordlist.is_some() || self.print || self.clip
+    }
+
+    /// Generate a password with the given spec, then print or clip it
+    ///
+    /// ## Errors
+    /// - If the password can't be generated
+    /// - If the password can't be copied to the clipboard
+    pub fn generate(&self, spec: &GeneratorSpec) -> LprsResult<String> {
+        let generated = spec.generate(self.wordlist.as_deref())?;
+        log::info!(
+            "Generated a password with {:.1} bits entropy",
+            generated.entropy
+        );
+        if self.print {
+            println!("{}", generated.password);
+        }
+        if self.clip {
+            utils::copy_to_clipboard(&generated.password)?;
+        }
+        Ok(generated.password)
+    }
+}
 
 #[derive(Debug, Args)]
 /// Generate command, used to generate a password
 pub struct Gen {
     /// The password length
-    #[arg(default_value_t = NonZeroU64::new(18).unwrap())]
+    #[arg(default_value_t = NonZeroU64::new(DEFAULT_LENGTH as u64).unwrap())]
     length: NonZeroU64,
 
     /// With uppercase letters (A-Z)
@@ -39,27 +98,109 @@ pub struct Gen {
     /// With symbols (!,# ...)
     #[arg(short, long)]
     symbols:   bool,
+    #[command(flatten)]
+    policy:    PasswordPolicy,
+
+    /// Generate a passphrase of random words instead, with the given number of
+    /// words
+    #[arg(short, long, value_name = "N", conflicts_with_all = ["length", "uppercase", "lowercase", "numbers", "symbols"])]
+    words:       Option<NonZeroUsize>,
+    /// The passphrase wordlist, a word per line or diceware lines (e.g. the EFF
+    /// large wordlist)
+    #[arg(long, value_name = "PATH")]
+    wordlist:    Option<PathBuf>,
+    /// The passphrase words separator
+    #[arg(long, value_name = "SEP", default_value = "-")]
+    separator:   String,
+    /// The passphrase words capitalization
+    #[arg(long, value_name = "MODE", default_value_t, value_enum)]
+    capitalize:  Capitalization,
+    /// Add random digits to random passphrase words
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    add_digits:  usize,
+    /// Add random symbols to random passphrase words
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    add_symbols: usize,
+
+    /// Generate a password from a template instead, e.g. `Cvcc-nnnn-Cvcc`.
+    /// `c`/`C` consonant, `v`/`V` vowel, `l`/`u` letter, `n` number, `s`
+    /// symbol, `a` letter or number, `x` any, `{N}` repeat, `\` escape
+    #[arg(short, long, value_name = "TEMPLATE", conflicts_with_all = ["length", "uppercase", "lowercase", "numbers", "symbols", "words"])]
+    pattern: Option<PatternGenerator>,
+
+    /// Print the password entropy in bits to the stderr
+    #[arg(long)]
+    entropy: bool,
 }
 
 impl LprsCommand for Gen {
     fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
-        print!(
-            "{}",
-            passwords::PasswordGenerator::new()
-                .length(self.length.get() as usize)
-                .uppercase_letters(self.uppercase)
-                .lowercase_letters(self.lowercase)
-                .numbers(self.numbers)
-                .symbols(self.symbols)
-                .strict(false)
-                .generate_one()
-                .expect("The length cannot be zero")
-        );
+        let generated = if let Some(pattern) = self.pattern {
+            pattern.generate()
+        } else if let Some(words) = self.words {
+            let wordlist = Wordlist::from_file(
+                self.wordlist
+                    .as_deref()
+                    .expect("The wordlist is required with the `--words` option"),
+            )?;
+            PassphraseGenerator {
+                words,
+                separator: self.separator,
+                capitalization: self.capitalize,
+                digits: self.add_digits,
+                symbols: self.add_symbols,
+            }
+            .generate(&wordlist)
+        } else {
+            CharsGenerator {
+                length:    NonZeroUsize::try_from(self.length)
+                    .map_err(|_| LprsError::Other("The password length is too big".to_owned()))?,
+                uppercase: self.uppercase,
+                lowercase: self.lowercase,
+                numbers:   self.numbers,
+                symbols:   self.symbols,
+                policy:    self.policy,
+            }
+            .generate()?
+        };
+
+        print!("{}", generated.password);
+        if self.entropy {
+            io::stdout().flush()?;
+            eprint!("\nEntropy: {:.1} bits\n", generated.entropy);
+        }
         Ok(())
     }
 
     fn validate_args(&self) -> LprsResult<()> {
-        if !(self.uppercase || self.lowercase || self.numbers || self.symbols) {
+        if self.pattern.is_some() {
+            if !self.policy.is_empty() {
+                return Err(LprsError::ArgsConflict(
+                    "The password policy can't be used with a pattern".to_owned(),
+                ));
+            }
+        } else if self.words.is_some() {
+            if !self.policy.is_empty() {
+                return Err(LprsError::ArgsConflict(
+                    "The password policy can't be used with a passphrase".to_owned(),
+                ));
+            }
+            if self.wordlist.is_none() {
+                return Err(LprsError::Other(
+                    "The passphrase needs a wordlist, pass it with the `--wordlist` option or set \
+                     it with `lprs config set gen.wordlist PATH`"
+                        .to_owned(),
+                ));
+            }
+        } else if !(self.uppercase
+            || self.lowercase
+            || self.numbers
+            || self.symbols
+            || self.policy.min_uppercase != 0
+            || self.policy.min_lowercase != 0
+            || self.policy.min_numbers != 0
+            || self.policy.min_symbols != 0)
+        {
             return Err(LprsError::Other(
                 "You need to enable at least one kind of characters".to_owned(),
             ));
@@ -14,7 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
 
-use std::{num::NonZeroUsize, str::FromStr};
+use std::{
+    borrow::Cow,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    num::NonZeroUsize,
+    path::PathBuf,
+    str::FromStr,
+};
 
 use clap::Args;
 use either::Either;
@@ -26,19 +32,24 @@ use crate::{
     RESERVED_FIELD_PREFIX,
     clap_parsers::either_parser,
     utils,
-    vault::{Vault, Vaults, cipher},
+    vault::{Vault, Vaults, cipher, item::ItemField, otpauth::OtpAuth, qr, strength::Strength},
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 enum VaultGetField {
     Index,
     Name,
+    Type,
     Username,
     Password,
+    Strength,
     Service,
     Note,
+    Folder,
     TotpSecret,
     TotpCode,
+    TotpQr,
+    Item(ItemField),
     Custom(String),
 }
 
@@ -49,30 +60,47 @@ impl FromStr for VaultGetField {
         Ok(match input.to_lowercase().as_str() {
             "index" => Self::Index,
             "name" => Self::Name,
+            "type" => Self::Type,
             "username" => Self::Username,
             "password" => Self::Password,
+            "strength" => Self::Strength,
             "service" => Self::Service,
             "note" => Self::Note,
+            "folder" => Self::Folder,
             "totp_secret" => Self::TotpSecret,
             "totp_code" => Self::TotpCode,
-            _ => Self::Custom(input.to_owned()),
+            "totp_qr" => Self::TotpQr,
+            field => {
+                ItemField::from_name(field)
+                    .map_or_else(|| Self::Custom(input.to_owned()), Self::Item)
+            }
         })
     }
 }
 
 impl VaultGetField {
-    /// Returns the field from the vault
-    pub fn get_from_vault<'a>(&self, vault: &'a Vault) -> Option<&'a str> {
+    /// Returns the field from the vault. The item fields of the vaults of
+    /// other types are custom fields, e.g. the `email` of a login
+    pub fn get_from_vault<'a>(&self, vault: &'a Vault) -> Option<Cow<'a, str>> {
+        let borrowed = |value: &'a Option<String>| value.as_deref().map(Cow::Borrowed);
         match self {
             Self::Index => None,
-            Self::Name => Some(&vault.name),
-            Self::Username => vault.username.as_deref(),
-            Self::Password => vault.password.as_deref(),
-            Self::Service => vault.service.as_deref(),
-            Self::Note => vault.note.as_deref(),
-            Self::TotpSecret => vault.totp_secret.as_deref(),
-            Self::TotpCode => None,
-            Self::Custom(custom_field) => vault.custom_fields.get(custom_field).map(|x| x.as_str()),
+            Self::Name => Some(Cow::Borrowed(&vault.name)),
+            Self::Type => Some(Cow::Owned(vault.item.item_type().to_string())),
+            Self::Username => borrowed(&vault.username),
+            Self::Password => borrowed(&vault.password),
+            Self::Service => borrowed(&vault.service),
+            Self::Note => borrowed(&vault.note),
+            Self::Folder => borrowed(&vault.folder),
+            Self::TotpSecret => borrowed(&vault.totp_secret),
+            Self::Strength | Self::TotpCode | Self::TotpQr => None,
+            Self::Item(field) => {
+                vault
+                    .item
+                    .field(*field)
+                    .or_else(|| vault.custom_fields.get(field.name()).map(Cow::from))
+            }
+            Self::Custom(custom_field) => vault.custom_fields.get(custom_field).map(Cow::from),
         }
     }
 
@@ -81,12 +109,17 @@ impl VaultGetField {
         match self {
             Self::Index => "index",
             Self::Name => "name",
+            Self::Type => "type",
             Self::Username => "username",
             Self::Password => "password",
+            Self::Strength => "strength",
             Self::Service => "service",
             Self::Note => "note",
+            Self::Folder => "folder",
             Self::TotpSecret => "totp_secret",
             Self::TotpCode => "totp_code",
+            Self::TotpQr => "totp_qr",
+            Self::Item(field) => field.name(),
             Self::Custom(field) => field.as_str(),
         }
     }
@@ -101,12 +134,23 @@ pub struct Get {
 
     /// A Specific field to get.
     ///
-    /// Can be [name, username, password, service, note, totp_secret, totp_code,
-    /// "string"]
+    /// Can be [name, type, username, password, strength, service, note,
+    /// folder, totp_secret, totp_code, totp_qr, "string"]
     ///
-    /// where the string means a custom field
+    /// where the string means a custom field. And the fields of the typed
+    /// items, card: [number, holder, expiry, cvv, brand], identity:
+    /// [full_name, email, phone, address], ssh-key: [private_key, public_key,
+    /// fingerprint] and api-token: [token, expiry]
     #[arg(value_parser = VaultGetField::from_str)]
-    field: Option<VaultGetField>,
+    field:        Option<VaultGetField>,
+    /// Write the `totp_qr` field as a PNG image to the given path instead of
+    /// printing it in the terminal
+    #[arg(short, long, value_name = "PNG_PATH")]
+    output:       Option<PathBuf>,
+    /// Show the secrets (password, TOTP secret, secret custom fields and the
+    /// item secrets) of the whole vault instead of masking them
+    #[arg(long, conflicts_with = "field")]
+    show_secrets: bool,
 }
 
 impl LprsCommand for Get {
@@ -119,9 +163,17 @@ impl LprsCommand for Get {
                 print!("{index}");
                 return Ok(());
             }
+            if field == VaultGetField::Strength {
+                let password = vault.password.as_deref().ok_or_else(|| {
+                    LprsError::Other("There is no password to get its strength".to_owned())
+                })?;
+                print!("{}", Strength::of(password));
+                return Ok(());
+            }
             if field == VaultGetField::TotpCode {
                 if let Some(ref totp_secret) = vault.totp_secret {
-                    let totp_code = cipher::totp_now(totp_secret, &vault.totp_hash)?.0;
+                    let totp_code =
+                        cipher::totp_now(totp_secret, &vault.totp_hash, vault.totp_digits)?.0;
                     print!("{totp_code}");
                     return Ok(());
                 } else {
@@ -131,6 +183,19 @@ impl LprsCommand for Get {
                 }
             }
 
+            if field == VaultGetField::TotpQr {
+                let uri = OtpAuth::from_vault(vault)
+                    .ok_or_else(|| {
+                        LprsError::Other("There is no TOTP secret to get TOTP QR code".to_owned())
+                    })?
+                    .to_uri();
+                if let Some(path) = self.output {
+                    return qr::write_png(&uri, &path);
+                }
+                println!("{}", qr::terminal_qr(&uri)?);
+                return Ok(());
+            }
+
             if let Some(value) = field.get_from_vault(vault) {
                 print!("{value}")
             } else {
@@ -142,13 +207,48 @@ impl LprsCommand for Get {
             }
         } else {
             if let Some(ref totp_secret) = vault.totp_secret {
-                let code = cipher::totp_now(totp_secret, &vault.totp_hash)?.0;
+                let code = cipher::totp_now(totp_secret, &vault.totp_hash, vault.totp_digits)?.0;
                 vault
                     .custom_fields
                     .insert(format!("{RESERVED_FIELD_PREFIX}TOTP Code"), code);
             }
-            println!("{vault}");
+            let codes = vault
+                .custom_totp_codes()?
+                .into_iter()
+                .map(|(key, code, _)| (format!("{RESERVED_FIELD_PREFIX}{key} Code"), code))
+                .collect::<Vec<_>>();
+            vault.custom_fields.extend(codes);
+            println!("{}", vault.display(self.show_secrets));
         }
         Ok(())
     }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        let Some(ref path) = self.output else {
+            return Ok(());
+        };
+
+        if self.field != Some(VaultGetField::TotpQr) {
+            return Err(LprsError::ArgsConflict(
+                "The `--output` option can only be used with the `totp_qr` field".to_owned(),
+            ));
+        }
+        if !path
+            .extension()
+            .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("png"))
+        {
+            return Err(LprsError::Io(IoError::new(
+                IoErrorKind::InvalidInput,
+                format!("file `{}` is not a png file", path.display()),
+            )));
+        }
+        if path.exists() {
+            return Err(LprsError::Io(IoError::new(
+                IoErrorKind::AlreadyExists,
+                format!("file `{}` is already exists", path.display()),
+            )));
+        }
+
+        Ok(())
+    }
 }
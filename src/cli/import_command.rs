@@ -17,25 +17,67 @@
 use std::{
     fs,
     io::{self, Error as IoError, ErrorKind as IoErrorKind, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use clap::Args;
 use sha2::Digest;
 
-use crate::{LprsCommand, LprsError, LprsResult, utils, vault::Vaults};
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    utils,
+    vault::{
+        Format,
+        Vault,
+        Vaults,
+        backup,
+        bitwarden::BitWardenVaults,
+        csv::{self, CsvColumn, CsvPreset},
+        google_auth,
+        kdbx,
+        plaintext,
+        qr,
+    },
+};
 
 /// Import command, used to import vaults from the exported files.
 #[derive(Debug, Args)]
 pub struct Import {
     /// The file path to import from. Use `-` to import from the stdin.
-    path: PathBuf,
+    path:   PathBuf,
+    /// The format of the imported file
+    #[arg(short, long, value_enum, default_value_t)]
+    format: Format,
 
     /// Decryption password of the imported vaults, if there is not, will use
     /// the master password
+    ///
+    /// For the `kdbx` format, it's the database password, you will be prompted
+    /// for it if there is no key file
     #[arg(short = 'p', long)]
     #[allow(clippy::option_option)]
     decryption_password: Option<Option<String>>,
+    /// The key file of the `kdbx` database
+    #[arg(short, long, value_name = "PATH")]
+    key_file:            Option<PathBuf>,
+    /// The CSV layout of the exporting manager, for the `csv` format
+    #[arg(long, value_enum)]
+    preset:              Option<CsvPreset>,
+    /// Map the CSV columns to the vault fields, for the `csv` format. e.g.
+    /// `name=title,username=login`
+    ///
+    /// The fields are name, username, password, service, note, folder and
+    /// totp, other fields are custom fields. The unmapped columns will be
+    /// custom fields.
+    #[arg(long, value_name = "FIELD=COLUMN", value_delimiter = ',')]
+    map:                 Vec<CsvColumn>,
+    /// Import the `lprs-json` vaults even if some of their fields can't be
+    /// decrypted, the failed fields are dropped and reported. A vault that
+    /// can't be imported at all (e.g. a wrong password) is still an error
+    #[arg(long)]
+    lenient:             bool,
 }
 
 impl LprsCommand for Import {
@@ -46,29 +88,113 @@ impl LprsCommand for Import {
             vault_manager.vaults_file.display()
         );
 
-        let decryption_key: Option<[u8; 32]> =
-            utils::user_secret(self.decryption_password, "Decryption password:", false)?
-                .map(|p| sha2::Sha256::digest(p).into());
+        let vaults_len = match self.format {
+            Format::Lprs | Format::LprsJson => {
+                let lprs_data = read_input(&self.path)?;
+                let vaults = if plaintext::is_plaintext(&lprs_data) {
+                    log::info!("Importing a plaintext export");
+                    plaintext::import(&lprs_data)?
+                } else {
+                    let decryption_key: [u8; 32] = utils::user_secret(
+                        self.decryption_password,
+                        "Decryption password:",
+                        false,
+                    )?
+                    .map_or(vault_manager.master_password, |p| {
+                        sha2::Sha256::digest(p).into()
+                    });
 
-        // Read from stdin if path is "-", otherwise read from the specified
-        // file
-        let json_data = if self.path.as_os_str() == "-" {
-            let mut buf = Vec::new();
-            io::stdin().read_to_end(&mut buf)?;
-            buf
-        } else {
-            fs::read(self.path)?
+                    if backup::is_backup(&lprs_data) {
+                        log::info!("Importing an encrypted backup");
+                        let vaults = backup::import(&lprs_data, &decryption_key)?;
+                        vaults.iter().try_for_each(Vault::validate)?;
+                        vaults
+                    } else {
+                        let (vaults, issues) =
+                            Vaults::json_reload(&decryption_key, &lprs_data, self.lenient)?;
+                        if !issues.is_empty() {
+                            for issue in &issues {
+                                eprintln!("{issue}");
+                            }
+                            eprintln!(
+                                "{} field{s} can't be decrypted and {are} dropped from the \
+                                 imported vaults",
+                                issues.len(),
+                                s = if issues.len() >= 2 { "s" } else { "" },
+                                are = if issues.len() >= 2 { "are" } else { "is" },
+                            );
+                        }
+                        vaults
+                    }
+                };
+                let vaults_len = vaults.len();
+                vault_manager.vaults = vaults;
+                vaults_len
+            }
+            Format::BitWarden => {
+                let vaults = BitWardenVaults::import(&read_input(&self.path)?)?;
+                vaults.iter().try_for_each(Vault::validate)?;
+                let vaults_len = vaults.len();
+                vault_manager.vaults.extend(vaults);
+                vaults_len
+            }
+            Format::Kdbx => {
+                let password = utils::user_secret(
+                    self.decryption_password
+                        .or_else(|| self.key_file.is_none().then_some(None)),
+                    "KDBX password:",
+                    false,
+                )?;
+                let vaults = kdbx::import(
+                    &read_input(&self.path)?,
+                    password.as_deref(),
+                    self.key_file.as_deref(),
+                )?;
+                vaults.iter().try_for_each(Vault::validate)?;
+                let vaults_len = vaults.len();
+                vault_manager.vaults.extend(vaults);
+                vaults_len
+            }
+            Format::Csv => {
+                let vaults = csv::import(&read_input(&self.path)?, self.preset, &self.map)?;
+                vaults.iter().try_for_each(Vault::validate)?;
+                let vaults_len = vaults.len();
+                vault_manager.vaults.extend(vaults);
+                vaults_len
+            }
+            Format::GoogleAuthMigration => {
+                let migration_data = if is_png(&self.path) {
+                    qr::read_png(&self.path)?
+                } else {
+                    String::from_utf8(read_input(&self.path)?)?
+                };
+                let (vaults, skipped) = google_auth::migration_vaults(&migration_data)?;
+                for reason in skipped {
+                    eprintln!("Skipping {reason}");
+                }
+                let mut vaults_len = 0;
+                for vault in vaults {
+                    let same_name = vault_manager.vaults.iter().filter(|v| v.name == vault.name);
+                    if same_name.clone().any(|v| v.username == vault.username) {
+                        eprintln!(
+                            "Skipping `{}`: there is already a vault for this account",
+                            vault.name
+                        );
+                        continue;
+                    }
+                    if same_name.count() != 0 {
+                        eprintln!(
+                            "Warning: There is already a vault named `{}`, the account will be \
+                             imported with the same name",
+                            vault.name
+                        );
+                    }
+                    vault_manager.vaults.push(vault);
+                    vaults_len += 1;
+                }
+                vaults_len
+            }
         };
-
-        let vaults = Vaults::json_reload(
-            decryption_key
-                .as_ref()
-                .unwrap_or(&vault_manager.master_password),
-            &json_data,
-        )?;
-        let vaults_len = vaults.len();
-
-        vault_manager.vaults = vaults;
         vault_manager.try_export()?;
 
         println!(
@@ -79,20 +205,54 @@ impl LprsCommand for Import {
     }
 
     fn validate_args(&self) -> LprsResult<()> {
+        if self.decryption_password.is_some() && !self.format.is_encrypted() {
+            return Err(LprsError::ArgsConflict(
+                "The decryption password can only be used with the `lprs`, `lprs-json` and `kdbx` \
+                 formats"
+                    .to_owned(),
+            ));
+        }
+        if let Some(ref key_file) = self.key_file {
+            if self.format != Format::Kdbx {
+                return Err(LprsError::ArgsConflict(
+                    "The key file can only be used with the `kdbx` format".to_owned(),
+                ));
+            }
+            if !key_file.is_file() {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("key file `{}` not found", key_file.display()),
+                )));
+            }
+        }
+        if self.lenient && !matches!(self.format, Format::Lprs | Format::LprsJson) {
+            return Err(LprsError::ArgsConflict(
+                "The lenient import can only be used with the `lprs` and `lprs-json` formats"
+                    .to_owned(),
+            ));
+        }
+        if (self.preset.is_some() || !self.map.is_empty()) && self.format != Format::Csv {
+            return Err(LprsError::ArgsConflict(
+                "The preset and the columns mapping can only be used with the `csv` format"
+                    .to_owned(),
+            ));
+        }
         // Skip checking the path if we will read from the stdin
         if self.path.as_os_str() == "-" {
             return Ok(());
         }
 
-        if !self
-            .path
-            .extension()
-            .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("json"))
-        {
-            return Err(LprsError::Io(IoError::new(
-                IoErrorKind::InvalidInput,
-                format!("file `{}` is not a json file", self.path.display()),
-            )));
+        if let Some(extension) = self.format.extension() {
+            if !self
+                .path
+                .extension()
+                .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(extension))
+            {
+                return Err(LprsError::Io(IoError::new(
+                    IoErrorKind::InvalidInput,
+                    format!("file `{}` is not a {extension} file", self.path.display()),
+                )));
+            }
         }
         if !self.path.exists() {
             return Err(LprsError::Io(IoError::new(
@@ -110,3 +270,20 @@ impl LprsCommand for Import {
         Ok(())
     }
 }
+
+/// Returns true if the given path is a PNG image
+fn is_png(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("png"))
+}
+
+/// Read the content of the given path, from the stdin if the path is `-`
+fn read_input(path: &Path) -> LprsResult<Vec<u8>> {
+    if path.as_os_str() == "-" {
+        let mut buf = Vec::new();
+        io::stdin().read_to_end(&mut buf)?;
+        Ok(buf)
+    } else {
+        fs::read(path).map_err(LprsError::from)
+    }
+}
@@ -30,13 +30,17 @@ use crate::{
 pub struct List {
     /// Filter the select list
     #[arg(short, long, value_name = "TEXT")]
-    filter: Option<String>,
+    filter:       Option<String>,
     /// Enable regex when use `--filter` option
     #[arg(short, long)]
-    regex:  bool,
+    regex:        bool,
     /// Returns the output as `json` list of vaults
     #[arg(long)]
-    json:   bool,
+    json:         bool,
+    /// Show the secrets (password, TOTP secret, secret custom fields and the
+    /// item secrets) of the selected vault instead of masking them
+    #[arg(long, conflicts_with = "json")]
+    show_secrets: bool,
 }
 
 impl LprsCommand for List {
@@ -71,6 +75,11 @@ impl LprsCommand for List {
                 || v.note
                     .as_deref()
                     .is_some_and(|n| re.is_match(&n.to_lowercase()))
+                || v.folder
+                    .as_deref()
+                    .is_some_and(|f| re.is_match(&f.to_lowercase()))
+                || v.public_custom_fields()
+                    .any(|value| re.is_match(&value.to_lowercase()))
         });
 
         if self.json {
@@ -113,14 +122,26 @@ impl LprsCommand for List {
                 .expect("The index is correct");
 
             if let Some(ref totp_secret) = vault.totp_secret {
-                let (code, remaining) = cipher::totp_now(totp_secret, &vault.totp_hash)?;
+                let (code, remaining) =
+                    cipher::totp_now(totp_secret, &vault.totp_hash, vault.totp_digits)?;
                 vault.custom_fields.insert(
                     format!("{RESERVED_FIELD_PREFIX}TOTP Code"),
                     format!("{code} ({remaining}s remaining)"),
                 );
             }
+            let codes = vault
+                .custom_totp_codes()?
+                .into_iter()
+                .map(|(key, code, remaining)| {
+                    (
+                        format!("{RESERVED_FIELD_PREFIX}{key} Code"),
+                        format!("{code} ({remaining}s remaining)"),
+                    )
+                })
+                .collect::<Vec<_>>();
+            vault.custom_fields.extend(codes);
 
-            println!("{vault}",);
+            println!("{}", vault.display(self.show_secrets));
         }
 
         Ok(())
@@ -16,21 +16,39 @@
 
 use std::{fs, path::PathBuf};
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use sha2::{Digest, Sha256};
 
-use crate::{LprsCommand, LprsResult, impl_commands, utils, vault::Vaults};
+use crate::{
+    LprsCommand,
+    LprsResult,
+    config::{self, global::GlobalConfig, profiles::ProfilesConfig},
+    impl_commands,
+    utils,
+    vault::Vaults,
+};
 
 /// Add command, used to add new vault to the vaults file
 pub mod add_command;
+/// Attach command, used to manage the vaults attachments
+pub mod attach_command;
+/// Audit command, used to find the weak, reused and old passwords
+pub mod audit_command;
 /// Change master password, reencrypt the vaults with new password
 pub mod change_master_password_command;
 /// Clean command, used to clean the vaults file (remove all vaults)
 pub mod clean_command;
 /// Generate shell completion
 pub mod completion_command;
+/// Config command, used to manage the global config
+pub mod config_command;
+/// Diff command, used to compare the vaults with an export or another vaults
+/// file
+pub mod diff_command;
 /// Edit command, used to edit the vault content
 pub mod edit_command;
+/// Edit the vaults in the user editor as TOML
+pub mod editor;
 /// Export command, used to export the vaults
 pub mod export_command;
 /// Generate command, used to generate a password
@@ -41,11 +59,20 @@ pub mod get_command;
 pub mod import_command;
 /// List command, used to list the vaults and search
 pub mod list_command;
+/// Policy command, used to manage the password policies
+pub mod policy_command;
+/// Profile command, used to manage the profiles
+pub mod profile_command;
 /// Remove command, used to remove vault from the vaults file
 pub mod remove_command;
+/// TUI command, used to manage the vaults in a full-screen terminal UI
+pub mod tui_command;
+/// The interactive wizard of the `add` and `edit` commands
+pub mod wizard;
 
 /// The lprs commands
 #[derive(Debug, clap::Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Add new vault
     Add(add_command::Add),
@@ -63,17 +90,31 @@ pub enum Commands {
     Gen(gen_command::Gen),
     /// Get a entire vault or single field from it
     Get(get_command::Get),
+    /// Manage the vaults attachments, the files encrypted with them
+    Attach(attach_command::Attach),
     /// Export the vaults
     Export(export_command::Export),
     /// Import vaults
     Import(import_command::Import),
+    /// Compare the vaults with an export or another vaults file
+    Diff(diff_command::Diff),
+    /// Audit the vaults passwords, the weak, reused and old ones
+    Audit(audit_command::Audit),
+    /// Manage the vaults in a full-screen terminal UI
+    Tui(tui_command::Tui),
     /// Change master password, reencrypt the vaults with new password
     ChangeMasterPassword(change_master_password_command::ChangeMasterPassword),
+    /// Manage the password policies of the vaults and the service domains
+    Policy(policy_command::Policy),
+    /// Manage the profiles, the named vaults files
+    Profile(profile_command::Profile),
+    /// Manage the global config, the commands defaults
+    Config(config_command::Config),
     /// Generate shell completion
     Completion(completion_command::Completion),
 }
 
-impl_commands!(Commands, Add Remove List Clean Edit Gen Get Export Import ChangeMasterPassword Completion);
+impl_commands!(Commands, Add Remove List Clean Edit Gen Get Attach Export Import Diff Audit Tui ChangeMasterPassword Policy Profile Config Completion);
 
 /// Header message, used in the help message
 const HEADER: &str = r#"Copyright (C) 2024 Awiteb <a@4rs.nl>
@@ -100,6 +141,9 @@ pub struct Cli {
     /// The master password, or you will prompt it
     #[arg(short, long)]
     pub master_password: Option<String>,
+    /// The profile to use, or the default profile if there is one
+    #[arg(long, env = "LPRS_PROFILE")]
+    pub profile:         Option<String>,
 
     #[command(subcommand)]
     /// The provided command to run
@@ -107,6 +151,44 @@ pub struct Cli {
 }
 
 impl Cli {
+    /// Parse the CLI arguments, with the commands defaults of the global
+    /// config, then the active profile, then the environment variables. The
+    /// vaults file of the profile is used if there is no given vaults file
+    ///
+    /// Exits with the clap error if the arguments are invalid
+    ///
+    /// # Errors
+    /// - If the global config or the profiles file is invalid
+    /// - If the active profile doesn't exist
+    /// - If there is invalid defaults
+    pub fn parse_with_config() -> LprsResult<Self> {
+        // The profile is needed before parsing, to set its defaults
+        let profile_name = Self::command()
+            .ignore_errors(true)
+            .try_get_matches()
+            .ok()
+            .and_then(|matches| matches.get_one::<String>("profile").cloned());
+        let profiles = ProfilesConfig::load()?;
+        let active_profile = profiles.active_profile(profile_name.as_deref())?;
+
+        let mut command = config::apply_defaults(Self::command(), &GlobalConfig::load()?.defaults)?;
+        if let Some((_, profile)) = active_profile {
+            command = config::apply_defaults(command, &profile.defaults)?;
+        }
+        let env_defaults = config::env_defaults(&command)?;
+        command = config::apply_defaults(command, &env_defaults)?;
+        let mut lprs_cli =
+            Self::from_arg_matches(&command.get_matches()).unwrap_or_else(|err| err.exit());
+
+        if let Some((name, profile)) = active_profile {
+            lprs_cli.profile = Some(name.to_owned());
+            lprs_cli
+                .vaults_file
+                .get_or_insert_with(|| profile.path.clone());
+        }
+        Ok(lprs_cli)
+    }
+
     /// Run the cli
     ///
     /// # Errors
@@ -118,6 +200,9 @@ impl Cli {
     pub fn run(self) -> LprsResult<()> {
         let vaults_file = if let Some(path) = self.vaults_file {
             log::info!("Using the given vaults file");
+            if let Some(profile) = &self.profile {
+                log::info!("The active profile: {profile}");
+            }
             if let Some(parent) = path.parent() {
                 if parent.to_str() != Some("") && !parent.exists() {
                     log::info!(
@@ -141,8 +226,13 @@ impl Cli {
 
         let vault_manager = if matches!(
             self.command,
-            Commands::Clean(..) | Commands::Gen(..) | Commands::Completion(..)
-        ) {
+            Commands::Clean(..)
+                | Commands::Gen(..)
+                | Commands::Profile(..)
+                | Commands::Config(..)
+                | Commands::Completion(..)
+        ) || matches!(&self.command, Commands::Policy(policy) if !policy.need_vaults())
+        {
             log::info!("Running command that don't need the vault manager");
             // Returns empty vault manager for those commands don't need it
             Vaults {
@@ -0,0 +1,272 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::num::NonZeroUsize;
+
+use clap::{Args, Subcommand};
+use either::Either;
+
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    clap_parsers::either_parser,
+    config::policies::{self, PoliciesConfig},
+    generator::policy::PasswordPolicy,
+    impl_commands,
+    utils,
+    vault::Vaults,
+};
+
+/// The policy subcommands
+#[derive(Debug, Subcommand)]
+pub enum PolicyCommands {
+    /// List the domains and the vaults policies [alias `ls`]
+    #[command(alias = "ls")]
+    List(List),
+    /// Show the policy of a vault or a domain
+    Get(Get),
+    /// Set the policy of a vault or a domain, replaces the existing one
+    Set(Set),
+    /// Remove the policy of a vault or a domain [alias `rm`]
+    #[command(alias = "rm")]
+    Remove(Remove),
+}
+
+impl_commands!(PolicyCommands, List Get Set Remove);
+
+#[derive(Debug, Args)]
+/// Policy command, used to manage the password policies
+pub struct Policy {
+    /// The policy command
+    #[command(subcommand)]
+    command: PolicyCommands,
+}
+
+/// The policy owner, a vault or a service domain
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct PolicyTarget {
+    /// The vault, index or name
+    #[arg(long, value_name = "INDEX-or-NAME", value_parser = either_parser::<NonZeroUsize, String>)]
+    vault:  Option<Either<NonZeroUsize, String>>,
+    /// The service domain, e.g. `example.com`. Used for the vaults with this
+    /// service domain or its subdomains, unless they have their own policy
+    #[arg(long)]
+    domain: Option<String>,
+}
+
+#[derive(Debug, Args)]
+/// List the policies
+pub struct List {
+    /// Returns the output as `json`
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+/// Show a policy
+pub struct Get {
+    #[command(flatten)]
+    target: PolicyTarget,
+    /// Returns the output as `json`
+    #[arg(long)]
+    json:   bool,
+}
+
+#[derive(Debug, Args)]
+/// Set a policy
+pub struct Set {
+    #[command(flatten)]
+    target: PolicyTarget,
+    #[command(flatten)]
+    policy: PasswordPolicy,
+}
+
+#[derive(Debug, Args)]
+/// Remove a policy
+pub struct Remove {
+    #[command(flatten)]
+    target: PolicyTarget,
+}
+
+impl PolicyTarget {
+    /// Returns the domain host, the domain can be a URL
+    ///
+    /// ## Errors
+    /// - If the domain is invalid
+    fn domain(&self) -> Option<LprsResult<String>> {
+        self.domain.as_deref().map(|domain| {
+            policies::service_host(domain)
+                .ok_or_else(|| LprsError::Other(format!("Invalid domain `{domain}`")))
+        })
+    }
+}
+
+impl Policy {
+    /// Returns true if the command needs the vaults, the domains policies
+    /// don't need them
+    pub const fn need_vaults(&self) -> bool {
+        match &self.command {
+            PolicyCommands::List(_) => true,
+            PolicyCommands::Get(Get { target, .. })
+            | PolicyCommands::Set(Set { target, .. })
+            | PolicyCommands::Remove(Remove { target }) => target.vault.is_some(),
+        }
+    }
+}
+
+impl LprsCommand for Policy {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        self.command.run(vault_manager)
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        self.command.validate_args()
+    }
+}
+
+impl LprsCommand for List {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        let policies_config = PoliciesConfig::load()?;
+        let vaults_policies = vault_manager
+            .vaults
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, vault)| vault.policy.as_ref().map(|p| (idx + 1, &vault.name, p)));
+
+        if self.json {
+            let vaults_policies: Vec<_> = vaults_policies
+                .map(|(index, name, policy)| {
+                    serde_json::json!({"index": index, "name": name, "policy": policy})
+                })
+                .collect();
+            print!(
+                "{}",
+                serde_json::json!({"domains": policies_config.domains, "vaults": vaults_policies})
+            );
+            return Ok(());
+        }
+
+        let mut lines: Vec<String> = policies_config
+            .domains
+            .iter()
+            .map(|(domain, policy)| format!("Domain {domain}: {policy}"))
+            .collect();
+        lines.extend(
+            vaults_policies.map(|(index, name, policy)| format!("Vault #{index} {name}: {policy}")),
+        );
+        if lines.is_empty() {
+            return Err(LprsError::Other(
+                "Looks like there is no policies to list".to_owned(),
+            ));
+        }
+        println!("{}", lines.join("\n"));
+        Ok(())
+    }
+}
+
+impl LprsCommand for Get {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        let policies_config = PoliciesConfig::load()?;
+        let policy = if let Some(domain) = self.target.domain() {
+            let domain = domain?;
+            policies_config.domains.get(&domain).ok_or_else(|| {
+                LprsError::Other(format!("The domain `{domain}` has no password policy"))
+            })?
+        } else {
+            let location = self.target.vault.as_ref().expect("The vault or the domain");
+            let (_, vault) = utils::vault_by_index_or_name(location, &mut vault_manager.vaults)?;
+            policies_config.vault_policy(vault).ok_or_else(|| {
+                LprsError::Other(format!("The vault `{}` has no password policy", vault.name))
+            })?
+        };
+
+        if self.json {
+            print!("{}", serde_json::to_string(policy)?);
+        } else {
+            println!("{policy}");
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Set {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        if let Some(domain) = self.target.domain() {
+            let mut policies_config = PoliciesConfig::load()?;
+            policies_config.domains.insert(domain?, self.policy);
+            return policies_config.save();
+        }
+
+        let location = self.target.vault.as_ref().expect("The vault or the domain");
+        let (_, vault) = utils::vault_by_index_or_name(location, &mut vault_manager.vaults)?;
+        vault.policy = Some(self.policy);
+        vault_manager.try_export()
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        if self.policy.is_empty() {
+            return Err(LprsError::Other(
+                "The policy must have at least one rule".to_owned(),
+            ));
+        }
+        self.policy.length(0)?;
+        if let Some(max_length) = self.policy.max_length {
+            if max_length == 0 {
+                return Err(LprsError::Other(
+                    "The policy maximum length can't be zero".to_owned(),
+                ));
+            }
+            let min_count = self.policy.min_uppercase
+                + self.policy.min_lowercase
+                + self.policy.min_numbers
+                + self.policy.min_symbols;
+            if max_length < min_count {
+                return Err(LprsError::Other(format!(
+                    "The policy needs at least {min_count} characters, but its maximum length is \
+                     {max_length}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Remove {
+    fn run(self, mut vault_manager: Vaults) -> LprsResult<()> {
+        if let Some(domain) = self.target.domain() {
+            let domain = domain?;
+            let mut policies_config = PoliciesConfig::load()?;
+            if policies_config.domains.remove(&domain).is_none() {
+                return Err(LprsError::Other(format!(
+                    "The domain `{domain}` has no password policy"
+                )));
+            }
+            return policies_config.save();
+        }
+
+        let location = self.target.vault.as_ref().expect("The vault or the domain");
+        let (_, vault) = utils::vault_by_index_or_name(location, &mut vault_manager.vaults)?;
+        if vault.policy.take().is_none() {
+            return Err(LprsError::Other(format!(
+                "The vault `{}` has no password policy",
+                vault.name
+            )));
+        }
+        vault_manager.try_export()
+    }
+}
@@ -0,0 +1,215 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{env, path::PathBuf};
+
+use clap::{Args, CommandFactory, Subcommand};
+
+use crate::{
+    LprsCommand,
+    LprsError,
+    LprsResult,
+    clap_parsers::default_parser,
+    cli::Cli,
+    config::{
+        self,
+        CommandsDefaults,
+        profiles::{Profile as ProfileConfig, ProfilesConfig},
+    },
+    impl_commands,
+    vault::Vaults,
+};
+
+/// The profile subcommands
+#[derive(Debug, Subcommand)]
+pub enum ProfileCommands {
+    /// List the profiles [alias `ls`]
+    #[command(alias = "ls")]
+    List(List),
+    /// Add new profile, or replace an existing one
+    Add(Add),
+    /// Remove a profile [alias `rm`]
+    #[command(alias = "rm")]
+    Remove(Remove),
+    /// Show or set the default profile
+    Default(Default),
+}
+
+impl_commands!(ProfileCommands, List Add Remove Default);
+
+#[derive(Debug, Args)]
+/// Profile command, used to manage the profiles
+pub struct Profile {
+    /// The profile command
+    #[command(subcommand)]
+    command: ProfileCommands,
+}
+
+#[derive(Debug, Args)]
+/// List the profiles
+pub struct List {
+    /// Returns the output as `json`
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+/// Add new profile
+pub struct Add {
+    /// The profile name
+    name:     String,
+    /// The vaults file of the profile
+    path:     PathBuf,
+    /// Make it the default profile
+    #[arg(short, long)]
+    default:  bool,
+    /// Set a default value of a command argument, `COMMAND.ARGUMENT=VALUE`.
+    /// Can be used multiple times
+    #[arg(short = 's', long = "set", value_name = "COMMAND.ARGUMENT=VALUE", value_parser = default_parser)]
+    defaults: Vec<(String, String, toml::Value)>,
+}
+
+#[derive(Debug, Args)]
+/// Remove a profile
+pub struct Remove {
+    /// The profile name
+    name: String,
+}
+
+#[derive(Debug, Args)]
+/// Show or set the default profile
+pub struct Default {
+    /// The new default profile, shows the current one if not given
+    name:  Option<String>,
+    /// Unset the default profile
+    #[arg(short, long, conflicts_with = "name")]
+    unset: bool,
+}
+
+impl LprsCommand for Profile {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        self.command.run(vault_manager)
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        self.command.validate_args()
+    }
+}
+
+impl LprsCommand for List {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let profiles = ProfilesConfig::load()?;
+        if self.json {
+            print!("{}", serde_json::to_string(&profiles)?);
+            return Ok(());
+        }
+        if profiles.profiles.is_empty() {
+            return Err(LprsError::Other(
+                "Looks like there is no profiles to list".to_owned(),
+            ));
+        }
+
+        for (name, profile) in &profiles.profiles {
+            let default = if profiles.default.as_ref() == Some(name) {
+                " (default)"
+            } else {
+                ""
+            };
+            println!("{name}{default}: {}", profile.path.display());
+            for (command, args) in &profile.defaults {
+                for (arg, value) in args {
+                    println!("    {command}.{arg} = {value}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Add {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let mut profiles = ProfilesConfig::load()?;
+        let path = if self.path.is_absolute() {
+            self.path
+        } else {
+            env::current_dir()?.join(self.path)
+        };
+
+        let mut defaults = CommandsDefaults::new();
+        for (command, arg, value) in self.defaults {
+            defaults.entry(command).or_default().insert(arg, value);
+        }
+        config::check_defaults(&defaults)?;
+        config::apply_defaults(Cli::command(), &defaults)?;
+
+        if profiles
+            .profiles
+            .insert(self.name.clone(), ProfileConfig { path, defaults })
+            .is_some()
+        {
+            log::info!("Replacing the existing profile `{}`", self.name);
+        }
+        if self.default {
+            profiles.default = Some(self.name);
+        }
+        profiles.save()
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(LprsError::EmptyValue);
+        }
+        if self.path.is_dir() {
+            return Err(LprsError::Other(format!(
+                "The vaults file `{}` is a directory",
+                self.path.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl LprsCommand for Remove {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let mut profiles = ProfilesConfig::load()?;
+        profiles.get(&self.name)?;
+        profiles.profiles.remove(&self.name);
+        if profiles.default.as_ref() == Some(&self.name) {
+            log::info!("Unsetting the default profile");
+            profiles.default = None;
+        }
+        profiles.save()
+    }
+}
+
+impl LprsCommand for Default {
+    fn run(self, _vault_manager: Vaults) -> LprsResult<()> {
+        let mut profiles = ProfilesConfig::load()?;
+        if self.unset {
+            profiles.default = None;
+        } else if let Some(name) = self.name {
+            profiles.get(&name)?;
+            profiles.default = Some(name);
+        } else {
+            match profiles.default {
+                Some(name) => println!("{name}"),
+                None => return Err(LprsError::Other("There is no default profile".to_owned())),
+            }
+            return Ok(());
+        }
+        profiles.save()
+    }
+}
@@ -0,0 +1,41 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use clap::Args;
+
+use crate::{
+    LprsCommand,
+    LprsResult,
+    tui,
+    vault::{Vaults, strength::StrengthPolicy},
+};
+
+#[derive(Debug, Args)]
+/// TUI command, used to manage the vaults in a full-screen terminal UI
+pub struct Tui {
+    #[command(flatten)]
+    strength: StrengthPolicy,
+}
+
+impl LprsCommand for Tui {
+    fn run(self, vault_manager: Vaults) -> LprsResult<()> {
+        tui::run(vault_manager, self.strength)
+    }
+
+    fn validate_args(&self) -> LprsResult<()> {
+        self.strength.validate()
+    }
+}
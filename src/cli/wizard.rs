@@ -0,0 +1,311 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::fmt;
+
+use clap::ValueEnum;
+use inquire::{Confirm, Select, Text, validator::Validation};
+
+use crate::{
+    LprsResult,
+    cli::gen_command::GeneratedPassword,
+    config::policies::PoliciesConfig,
+    generator::GeneratorSpec,
+    utils,
+    vault::{Vault, cipher, custom_field::FieldKind, strength::StrengthPolicy},
+};
+
+/// What to do with a vault secret, the password or the TOTP secret
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum SecretAction {
+    /// Keep the current secret
+    Keep,
+    /// Enter a new secret
+    Enter,
+    /// Generate a new password
+    Generate,
+    /// Remove the current secret
+    Remove,
+    /// Don't set a secret
+    Skip,
+}
+
+/// What to do with the vault custom fields
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum FieldsAction {
+    /// Add a new field or update an existing one
+    Add,
+    /// Remove a field
+    Remove,
+    /// Done editing the fields
+    Done,
+}
+
+impl fmt::Display for SecretAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Keep => "Keep the current one",
+            Self::Enter => "Enter a new one",
+            Self::Generate => "Generate a new one",
+            Self::Remove => "Remove it",
+            Self::Skip => "Don't set it",
+        })
+    }
+}
+
+impl fmt::Display for FieldsAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Add => "Add or update a field",
+            Self::Remove => "Remove a field",
+            Self::Done => "Done",
+        })
+    }
+}
+
+/// The interactive wizard of the `add` and `edit` commands, walks through the
+/// vault fields with prompts, the current values are the initial values
+pub struct VaultWizard<'a> {
+    /// The generated password options
+    pub generated: &'a GeneratedPassword,
+    /// The minimum strength of the entered password
+    pub strength:  &'a StrengthPolicy,
+    /// Accept the weak passwords without asking
+    pub force:     bool,
+}
+
+impl VaultWizard<'_> {
+    /// Walk through the vault fields, then apply the new values to the vault
+    ///
+    /// ## Errors
+    /// - If can't read the user input, e.g. the wizard is canceled
+    /// - If the password can't be generated
+    pub fn run(&self, vault: &mut Vault) -> LprsResult<()> {
+        vault.name = Text::new("Name:")
+            .with_initial_value(&vault.name)
+            .with_validator(|name: &str| {
+                Ok(if name.trim().is_empty() {
+                    Validation::Invalid("The vault name can't be empty".into())
+                } else {
+                    Validation::Valid
+                })
+            })
+            .prompt()?
+            .trim()
+            .to_string();
+        vault.username = optional_text("Username:", vault.username.as_deref())?;
+        vault.service = optional_text("Service:", vault.service.as_deref())?;
+        vault.folder = optional_text("Folder:", vault.folder.as_deref())?;
+        self.password(vault)?;
+        totp_secret(vault)?;
+        vault.note = optional_text("Note:", vault.note.as_deref())?;
+        custom_fields(vault)?;
+        Ok(())
+    }
+
+    /// Ask for the vault password, entering or generating a new one
+    fn password(&self, vault: &mut Vault) -> LprsResult<()> {
+        let actions = if vault.password.is_some() {
+            vec![
+                SecretAction::Keep,
+                SecretAction::Enter,
+                SecretAction::Generate,
+                SecretAction::Remove,
+            ]
+        } else {
+            vec![
+                SecretAction::Enter,
+                SecretAction::Generate,
+                SecretAction::Skip,
+            ]
+        };
+
+        match Select::new("Password:", actions).prompt()? {
+            SecretAction::Keep | SecretAction::Skip => {}
+            SecretAction::Remove => vault.set_password(None),
+            SecretAction::Enter => vault.set_password(Some(self.enter_password()?)),
+            SecretAction::Generate => {
+                let mut spec = Text::new("Generator spec:")
+                    .with_help_message(
+                        "`LENGTH[:CLASSES]`, `words:N[:SEPARATOR]` or `pattern:TEMPLATE`, empty \
+                         for 18 characters of all the classes",
+                    )
+                    .with_validator(|spec: &str| {
+                        Ok(match spec.trim() {
+                            "" => Validation::Valid,
+                            spec => {
+                                spec.parse::<GeneratorSpec>().map_or_else(
+                                    |err| Validation::Invalid(err.into()),
+                                    |_| Validation::Valid,
+                                )
+                            }
+                        })
+                    })
+                    .prompt()?
+                    .trim()
+                    .parse::<GeneratorSpec>()
+                    .unwrap_or_default();
+                if let Some(policy) = PoliciesConfig::load()?.vault_policy(vault) {
+                    spec = spec.with_policy(policy.clone())?;
+                }
+                vault.set_password(Some(self.generated.generate(&spec)?));
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask for a new password until it's strong enough or the user accepts
+    /// the weak one
+    fn enter_password(&self) -> LprsResult<String> {
+        loop {
+            let password = utils::secret_prompt("New password:", true, None)?;
+            let Some(weakness) = self.weakness(&password) else {
+                return Ok(password);
+            };
+            eprintln!("Warning: {weakness}");
+            if Confirm::new("Use this weak password anyway?")
+                .with_default(false)
+                .prompt()?
+            {
+                return Ok(password);
+            }
+        }
+    }
+
+    /// Returns why the password is weak, `None` if it's strong enough or the
+    /// wizard is forced
+    fn weakness(&self, password: &str) -> Option<String> {
+        if self.force {
+            return None;
+        }
+        self.strength.weakness(password)
+    }
+}
+
+/// Ask for an optional text, the empty text is `None`
+fn optional_text(message: &str, current: Option<&str>) -> LprsResult<Option<String>> {
+    let text = Text::new(message)
+        .with_initial_value(current.unwrap_or_default())
+        .with_help_message("Leave it empty to not set it")
+        .prompt()?;
+    Ok((!text.is_empty()).then_some(text))
+}
+
+/// Ask for the vault TOTP secret, it must be a valid base32 string
+fn totp_secret(vault: &mut Vault) -> LprsResult<()> {
+    let actions = if vault.totp_secret.is_some() {
+        vec![
+            SecretAction::Keep,
+            SecretAction::Enter,
+            SecretAction::Remove,
+        ]
+    } else {
+        vec![SecretAction::Skip, SecretAction::Enter]
+    };
+
+    match Select::new("TOTP secret:", actions).prompt()? {
+        SecretAction::Keep | SecretAction::Skip | SecretAction::Generate => {}
+        SecretAction::Remove => vault.totp_secret = None,
+        SecretAction::Enter => {
+            let validator = |secret: &str| {
+                Ok(if cipher::base32_decode(secret).is_ok() {
+                    Validation::Valid
+                } else {
+                    Validation::Invalid("Invalid TOTP secret, must be valid base32 string".into())
+                })
+            };
+            vault.totp_secret = Some(utils::secret_prompt(
+                "New TOTP secret:",
+                false,
+                Some(vec![Box::new(validator)]),
+            )?);
+        }
+    }
+    Ok(())
+}
+
+/// Add, update and remove the vault custom fields in a loop until the user
+/// is done
+fn custom_fields(vault: &mut Vault) -> LprsResult<()> {
+    loop {
+        let mut actions = vec![FieldsAction::Add];
+        if !vault.custom_fields.is_empty() {
+            actions.push(FieldsAction::Remove);
+        }
+        actions.push(FieldsAction::Done);
+        let message = if vault.custom_fields.is_empty() {
+            "Custom fields:".to_owned()
+        } else {
+            format!(
+                "Custom fields ({}):",
+                vault
+                    .custom_fields
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+
+        match Select::new(&message, actions).prompt()? {
+            FieldsAction::Done => return Ok(()),
+            FieldsAction::Add => {
+                let Some(key) = Text::new("Field key:")
+                    .with_help_message("Press esc to go back")
+                    .with_validator(|key: &str| {
+                        Ok(if key.is_empty() {
+                            Validation::Invalid("The field key can't be empty".into())
+                        } else if key.starts_with(crate::RESERVED_FIELD_PREFIX) {
+                            Validation::Invalid(
+                                format!(
+                                    "The field key can't start with `{}`",
+                                    crate::RESERVED_FIELD_PREFIX
+                                )
+                                .into(),
+                            )
+                        } else {
+                            Validation::Valid
+                        })
+                    })
+                    .prompt_skippable()?
+                else {
+                    continue;
+                };
+                let kinds = FieldKind::value_variants();
+                let current = vault.field_kind(&key);
+                let kind = Select::new("Field kind:", kinds.to_vec())
+                    .with_starting_cursor(kinds.iter().position(|k| *k == current).unwrap_or(0))
+                    .with_help_message("The secrets are masked, and never searched")
+                    .prompt()?;
+                let value = kind.prompt(&key)?;
+                vault.set_field_kind(&key, kind);
+                vault.custom_fields.insert(key, value);
+            }
+            FieldsAction::Remove => {
+                if let Some(key) = Select::new(
+                    "Field to remove:",
+                    vault.custom_fields.keys().cloned().collect(),
+                )
+                .with_help_message("↑↓ to move, enter to remove, esc to go back")
+                .prompt_skippable()?
+                {
+                    vault.custom_fields.remove(&key);
+                    vault.custom_field_kinds.remove(&key);
+                }
+            }
+        }
+    }
+}
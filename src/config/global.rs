@@ -0,0 +1,94 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::CommandsDefaults;
+use crate::{LprsError, LprsResult, utils};
+
+/// The global config file name, in the project config dir
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The global config file, the commands defaults used with all profiles
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct GlobalConfig {
+    /// The commands defaults, the command name tables with their arguments
+    pub defaults: CommandsDefaults,
+}
+
+impl GlobalConfig {
+    /// Load the global config file, returns an empty config if there is no
+    /// file
+    ///
+    /// ## Errors
+    /// - If the project dir can't be extracted from the OS
+    /// - If the file can't be read or it's invalid
+    pub fn load() -> LprsResult<Self> {
+        let config_file = utils::config_project_file(CONFIG_FILE)?;
+        if !config_file.exists() {
+            return Ok(Self::default());
+        }
+        log::debug!("Loading the config from: {}", config_file.display());
+        toml::from_str(&fs::read_to_string(&config_file)?).map_err(|err| {
+            LprsError::Config(format!(
+                "Invalid config file `{}`: {err}",
+                config_file.display()
+            ))
+        })
+    }
+
+    /// Save the global config file
+    ///
+    /// ## Errors
+    /// - If the project dir can't be extracted from the OS
+    /// - If the file can't be written
+    pub fn save(&self) -> LprsResult<()> {
+        let config_file = utils::config_project_file(CONFIG_FILE)?;
+        log::debug!("Saving the config to: {}", config_file.display());
+        fs::write(
+            config_file,
+            toml::to_string_pretty(self).map_err(|err| LprsError::Config(err.to_string()))?,
+        )
+        .map_err(LprsError::from)
+    }
+
+    /// Returns the default value of the command argument
+    pub fn get(&self, command: &str, argument: &str) -> Option<&toml::Value> {
+        self.defaults.get(command)?.get(argument)
+    }
+
+    /// Set the default value of the command argument
+    pub fn set(&mut self, command: String, argument: String, value: toml::Value) {
+        self.defaults
+            .entry(command)
+            .or_default()
+            .insert(argument, value);
+    }
+
+    /// Remove the default value of the command argument, returns the removed
+    /// value
+    pub fn unset(&mut self, command: &str, argument: &str) -> Option<toml::Value> {
+        let args = self.defaults.get_mut(command)?;
+        let value = args.remove(argument);
+        if args.is_empty() {
+            self.defaults.remove(command);
+        }
+        value
+    }
+}
@@ -0,0 +1,259 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{collections::BTreeMap, env};
+
+use clap::{Arg, ArgAction, Command};
+
+use crate::{LprsError, LprsResult};
+
+/// The global config module, the commands defaults of all profiles
+pub mod global;
+/// The policies module, the service domains password policies
+pub mod policies;
+/// The profiles module, the named vaults files with their defaults
+pub mod profiles;
+
+/// The commands arguments defaults, the command name to its arguments (the
+/// argument id or long name) with their default values
+pub type CommandsDefaults = BTreeMap<String, BTreeMap<String, toml::Value>>;
+
+/// The prefix of the commands defaults environment variables, the variable
+/// is `LPRS_<COMMAND>_<ARGUMENT>`, e.g. `LPRS_GEN_LENGTH`
+pub const ENV_PREFIX: &str = "LPRS_";
+
+/// The secret arguments, they can't have a default value so they are never
+/// stored in the config files nor read from the environment variables
+const SECRET_ARGUMENTS: &[&str] = &[
+    "password",
+    "new_password",
+    "master_password",
+    "totp_secret",
+    "encryption_password",
+    "decryption_password",
+    "card_number",
+    "card_cvv",
+    "token",
+    "custom",
+];
+
+/// The confirmation arguments, the flags that skip a safety check. They can't
+/// have a default value so they are always given explicitly
+const CONFIRMATION_ARGUMENTS: &[&str] = &[
+    "force",
+    "plaintext",
+    "i_understand",
+    "lenient",
+    "show_secrets",
+];
+
+/// Parse the default value, a TOML value or a string if it's not a valid one
+pub fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()))
+}
+
+/// Returns true if the argument name (its id or long name) is a secret, see
+/// [`SECRET_ARGUMENTS`]
+fn is_secret(arg_name: &str) -> bool {
+    SECRET_ARGUMENTS.contains(&arg_name.replace('-', "_").as_str())
+}
+
+/// Returns true if the argument name (its id or long name) is a
+/// confirmation, see [`CONFIRMATION_ARGUMENTS`]
+fn is_confirmation(arg_name: &str) -> bool {
+    CONFIRMATION_ARGUMENTS.contains(&arg_name.replace('-', "_").as_str())
+}
+
+/// Returns true if the argument can have a default value, the help, version,
+/// secret and confirmation arguments can't
+fn is_configurable(arg: &Arg) -> bool {
+    let is_denied = |arg_name: &str| is_secret(arg_name) || is_confirmation(arg_name);
+    !matches!(
+        arg.get_action(),
+        ArgAction::Help | ArgAction::HelpShort | ArgAction::HelpLong | ArgAction::Version
+    ) && !is_denied(arg.get_id().as_str())
+        && !arg.get_long().is_some_and(is_denied)
+}
+
+/// Returns the error of a confirmation argument default
+fn confirmation_error(option: &str, hint: &str) -> LprsError {
+    LprsError::Config(format!(
+        "The {option} skips a safety check, it can't have a default value. {hint}"
+    ))
+}
+
+/// Check that the defaults don't have a secret or confirmation argument, used
+/// before saving them
+///
+/// ## Errors
+/// - If there is a secret or confirmation argument default
+pub fn check_defaults(defaults: &CommandsDefaults) -> LprsResult<()> {
+    for (command_name, args) in defaults {
+        for arg_name in args.keys() {
+            if is_secret(arg_name) {
+                return Err(LprsError::Config(format!(
+                    "The `{command_name}.{arg_name}` option is a secret, it can't have a default \
+                     value"
+                )));
+            }
+            if is_confirmation(arg_name) {
+                return Err(confirmation_error(
+                    &format!("`{command_name}.{arg_name}` option"),
+                    "Pass it explicitly instead",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the environment variable name of the command argument
+pub fn env_name(command_name: &str, arg_name: &str) -> String {
+    format!("{ENV_PREFIX}{command_name}_{arg_name}")
+        .to_uppercase()
+        .replace('-', "_")
+}
+
+/// Returns the commands defaults from the environment variables, see
+/// [`ENV_PREFIX`]
+///
+/// ## Errors
+/// - If a variable value is not valid unicode
+/// - If a variable sets a confirmation argument
+pub fn env_defaults(command: &Command) -> LprsResult<CommandsDefaults> {
+    let mut defaults = CommandsDefaults::new();
+    for subcommand in command.get_subcommands() {
+        for arg in subcommand.get_arguments() {
+            let arg_name = arg.get_long().unwrap_or_else(|| arg.get_id().as_str());
+            let var_name = env_name(subcommand.get_name(), arg_name);
+            let Some(value) = env::var_os(&var_name) else {
+                continue;
+            };
+            if is_confirmation(arg_name) {
+                return Err(confirmation_error(
+                    &format!("`{var_name}` environment variable"),
+                    "Unset it and pass the option explicitly instead",
+                ));
+            }
+            if !is_configurable(arg) {
+                // The logger is not initialized yet, the defaults are applied before parsing
+                eprintln!(
+                    "Warning: The `{var_name}` environment variable is ignored, it's a secret"
+                );
+                continue;
+            }
+            let value = value.into_string().map_err(|_| {
+                LprsError::Config(format!("The `{var_name}` value is not valid unicode"))
+            })?;
+            log::debug!("Using the `{var_name}` environment variable");
+            defaults
+                .entry(subcommand.get_name().to_owned())
+                .or_default()
+                .insert(arg_name.to_owned(), parse_value(&value));
+        }
+    }
+    Ok(defaults)
+}
+
+/// Returns the default values of the TOML value, the arrays are multiple
+/// values
+///
+/// ## Errors
+/// - If the value is a table, or an array of arrays or tables
+fn default_values(value: &toml::Value) -> LprsResult<Vec<String>> {
+    match value {
+        toml::Value::String(value) => Ok(vec![value.clone()]),
+        toml::Value::Array(values) => {
+            values
+                .iter()
+                .map(|value| {
+                    match value {
+                        toml::Value::Array(_) | toml::Value::Table(_) => {
+                            Err(LprsError::Config(
+                                "The default values can't be nested arrays or tables".to_owned(),
+                            ))
+                        }
+                        value => default_values(value).map(|mut v| v.remove(0)),
+                    }
+                })
+                .collect()
+        }
+        toml::Value::Table(_) => {
+            Err(LprsError::Config(
+                "The default value can't be a table".to_owned(),
+            ))
+        }
+        value => Ok(vec![value.to_string()]),
+    }
+}
+
+/// Set the given defaults to the commands arguments, the user still can
+/// override them from the CLI.
+///
+/// ## Errors
+/// - If there is unknown command or argument
+/// - If there is a confirmation argument default
+/// - If the default value is not supported, see [`default_values`]
+pub fn apply_defaults(mut command: Command, defaults: &CommandsDefaults) -> LprsResult<Command> {
+    for (command_name, args) in defaults {
+        let subcommand = command
+            .find_subcommand(command_name)
+            .ok_or_else(|| LprsError::Config(format!("Unknown command `{command_name}`")))?;
+        // The canonical name, the given one can be an alias
+        let subcommand_name = subcommand.get_name().to_owned();
+        let mut arg_ids = Vec::with_capacity(args.len());
+        for (arg_name, value) in args {
+            if is_confirmation(arg_name) {
+                return Err(confirmation_error(
+                    &format!("`{command_name}.{arg_name}` option"),
+                    "Remove it from the config or the profiles file",
+                ));
+            }
+            // The old config files may have them
+            if is_secret(arg_name) {
+                eprintln!(
+                    "Warning: The `{command_name}.{arg_name}` default is ignored, it's a secret. \
+                     Unset it from the config"
+                );
+                continue;
+            }
+            let arg_id = subcommand
+                .get_arguments()
+                .filter(|arg| is_configurable(arg))
+                .find(|arg| arg.get_id() == arg_name || arg.get_long() == Some(arg_name))
+                .ok_or_else(|| {
+                    LprsError::Config(format!(
+                        "Unknown option `{arg_name}` of the command `{command_name}`"
+                    ))
+                })?
+                .get_id()
+                .clone();
+            arg_ids.push((arg_id, default_values(value)?));
+        }
+
+        log::debug!("Setting the `{command_name}` defaults: {arg_ids:?}");
+        command = command.mut_subcommand(subcommand_name, |mut subcommand| {
+            for (arg_id, values) in arg_ids {
+                subcommand = subcommand.mut_arg(arg_id, |arg| arg.default_values(values));
+            }
+            subcommand
+        });
+    }
+    Ok(command)
+}
@@ -0,0 +1,122 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{collections::BTreeMap, fs};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{LprsError, LprsResult, generator::policy::PasswordPolicy, utils, vault::Vault};
+
+/// The domains policies file name, in the project config dir
+pub const POLICIES_FILE: &str = "policies.toml";
+
+/// The service domains password policies
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoliciesConfig {
+    /// The domains with their policies, a domain policy is used for its
+    /// subdomains too
+    #[serde(default)]
+    pub domains: BTreeMap<String, PasswordPolicy>,
+}
+
+/// Returns the lowercase host of the service, the service can be a URL or a
+/// domain
+pub fn service_host(service: &str) -> Option<String> {
+    let service = service
+        .trim()
+        .split_once("://")
+        .map_or(service.trim(), |(_, rest)| rest);
+    let authority = service.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host)
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('.')
+        .to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Returns true if the host is the domain or one of its subdomains
+pub fn is_domain_host(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.ends_with('.'))
+}
+
+impl PoliciesConfig {
+    /// Load the policies file, returns an empty config if there is no file
+    ///
+    /// ## Errors
+    /// - If the project dir can't be extracted from the OS
+    /// - If the file can't be read or it's invalid
+    pub fn load() -> LprsResult<Self> {
+        let policies_file = utils::config_project_file(POLICIES_FILE)?;
+        if !policies_file.exists() {
+            return Ok(Self::default());
+        }
+        log::debug!("Loading the policies from: {}", policies_file.display());
+        toml::from_str(&fs::read_to_string(&policies_file)?).map_err(|err| {
+            LprsError::Config(format!(
+                "Invalid policies file `{}`: {err}",
+                policies_file.display()
+            ))
+        })
+    }
+
+    /// Save the policies file
+    ///
+    /// ## Errors
+    /// - If the project dir can't be extracted from the OS
+    /// - If the file can't be written
+    pub fn save(&self) -> LprsResult<()> {
+        let policies_file = utils::config_project_file(POLICIES_FILE)?;
+        log::debug!("Saving the policies to: {}", policies_file.display());
+        fs::write(
+            policies_file,
+            toml::to_string_pretty(self).map_err(|err| LprsError::Config(err.to_string()))?,
+        )
+        .map_err(LprsError::from)
+    }
+
+    /// Returns the policy of the service domain, the most specific domain wins
+    pub fn domain_policy(&self, service: &str) -> Option<(&str, &PasswordPolicy)> {
+        let host = service_host(service)?;
+        self.domains
+            .iter()
+            .filter(|(domain, _)| is_domain_host(&host, domain))
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(domain, policy)| (domain.as_str(), policy))
+    }
+
+    /// Returns the policy of the vault, its own policy or its service domain
+    /// policy
+    pub fn vault_policy<'a>(&'a self, vault: &'a Vault) -> Option<&'a PasswordPolicy> {
+        vault.policy.as_ref().or_else(|| {
+            vault
+                .service
+                .as_deref()
+                .and_then(|service| self.domain_policy(service))
+                .map(|(domain, policy)| {
+                    log::info!("Using the `{domain}` domain password policy");
+                    policy
+                })
+        })
+    }
+}
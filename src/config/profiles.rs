@@ -0,0 +1,109 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::CommandsDefaults;
+use crate::{LprsError, LprsResult, utils};
+
+/// The profiles file name, in the local project dir
+pub const PROFILES_FILE: &str = "profiles.toml";
+
+/// The profiles config file
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfilesConfig {
+    /// The default profile, used if there is no given profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default:  Option<String>,
+    /// The profiles
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named vaults file with its commands defaults
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// The vaults file of the profile
+    pub path:     PathBuf,
+    /// The commands defaults of the profile
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: CommandsDefaults,
+}
+
+impl ProfilesConfig {
+    /// Load the profiles config file, returns an empty config if there is no
+    /// file
+    ///
+    /// ## Errors
+    /// - If the project dir can't be extracted from the OS
+    /// - If the file can't be read or it's invalid
+    pub fn load() -> LprsResult<Self> {
+        let profiles_file = utils::local_project_file(PROFILES_FILE)?;
+        if !profiles_file.exists() {
+            return Ok(Self::default());
+        }
+        log::debug!("Loading the profiles from: {}", profiles_file.display());
+        toml::from_str(&fs::read_to_string(&profiles_file)?).map_err(|err| {
+            LprsError::Config(format!(
+                "Invalid profiles file `{}`: {err}",
+                profiles_file.display()
+            ))
+        })
+    }
+
+    /// Save the profiles config file
+    ///
+    /// ## Errors
+    /// - If the project dir can't be extracted from the OS
+    /// - If the file can't be written
+    pub fn save(&self) -> LprsResult<()> {
+        let profiles_file = utils::local_project_file(PROFILES_FILE)?;
+        log::debug!("Saving the profiles to: {}", profiles_file.display());
+        fs::write(
+            profiles_file,
+            toml::to_string_pretty(self).map_err(|err| LprsError::Config(err.to_string()))?,
+        )
+        .map_err(LprsError::from)
+    }
+
+    /// Returns the profile with the given name
+    ///
+    /// ## Errors
+    /// - If there is no profile with the given name
+    pub fn get(&self, name: &str) -> LprsResult<&Profile> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| LprsError::Config(format!("There is no profile named `{name}`")))
+    }
+
+    /// Returns the active profile, the given one or the default one. Returns
+    /// `None` if there is no given profile nor default profile
+    ///
+    /// ## Errors
+    /// - If the profile doesn't exist
+    pub fn active_profile<'a>(
+        &'a self,
+        name: Option<&'a str>,
+    ) -> LprsResult<Option<(&'a str, &'a Profile)>> {
+        name.or(self.default.as_deref())
+            .map(|name| self.get(name).map(|profile| (name, profile)))
+            .transpose()
+    }
+}
@@ -48,6 +48,28 @@ pub enum Error {
     EmptyValue,
     #[error("Base32 Error: {0}")]
     Base32(String),
+    #[error("Invalid otpauth URI: {0}")]
+    InvalidOtpAuth(String),
+    #[error("QR Code Error: {0}")]
+    Qr(String),
+    #[error("Google Authenticator Migration Error: {0}")]
+    GoogleAuthMigration(String),
+    #[error("KeePass Error: {0}")]
+    KeePass(String),
+    #[error("CSV Error: {0}")]
+    Csv(String),
+    #[error("Field Decryption Error: Some vaults fields can't be decrypted\n{0}")]
+    FieldDecryption(String),
+    #[error("Config Error: {0}")]
+    Config(String),
+    #[error("Audit Error: {0}")]
+    Audit(String),
+    #[error("Editor Error: {0}")]
+    Editor(String),
+    #[error("Invalid Item Error: {0}")]
+    InvalidItem(String),
+    #[error("Invalid Custom Field Error: {0}")]
+    InvalidCustomField(String),
     #[error("{0}")]
     Other(String),
 
@@ -0,0 +1,284 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The password generators, used by the `gen` command and to generate the
+//! vaults passwords
+
+use std::{num::NonZeroUsize, path::Path, str::FromStr};
+
+use rand::seq::{IndexedRandom, SliceRandom};
+
+use self::policy::PasswordPolicy;
+use crate::{LprsError, LprsResult};
+
+/// The passphrase (diceware) generator
+pub mod passphrase;
+/// The pattern (template) generator
+pub mod pattern;
+/// The password policy, the rules of the generated passwords
+pub mod policy;
+
+/// The symbols of the generated passwords
+pub const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+/// The digits of the generated passwords
+pub const DIGITS: &str = "0123456789";
+/// The uppercase letters of the generated passwords
+pub const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// The lowercase letters of the generated passwords
+pub const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// A generated password with its entropy
+#[derive(Debug)]
+pub struct Generated {
+    /// The generated password
+    pub password: String,
+    /// The password entropy in bits
+    pub entropy:  f64,
+}
+
+/// The default length of the generated passwords
+pub const DEFAULT_LENGTH: usize = 18;
+
+/// The random characters password generator
+#[derive(Debug, Clone)]
+pub struct CharsGenerator {
+    /// The password length, clamped to the policy length
+    pub length:    NonZeroUsize,
+    /// With uppercase letters (A-Z)
+    pub uppercase: bool,
+    /// With lowercase letters (a-z)
+    pub lowercase: bool,
+    /// With numbers (0-9)
+    pub numbers:   bool,
+    /// With symbols (!,# ...)
+    pub symbols:   bool,
+    /// The password policy
+    pub policy:    PasswordPolicy,
+}
+
+impl CharsGenerator {
+    /// Returns the enabled characters classes, with their name, characters
+    /// and minimum count. The policy exclusions are applied
+    ///
+    /// ## Errors
+    /// - If a class with a minimum count has no allowed characters
+    fn classes(&self) -> LprsResult<Vec<(&'static str, Vec<char>, usize)>> {
+        let symbols = self.policy.allowed_symbols.as_deref().unwrap_or(SYMBOLS);
+        let classes = [
+            (
+                "uppercase",
+                UPPERCASE,
+                self.uppercase,
+                self.policy.min_uppercase,
+            ),
+            (
+                "lowercase",
+                LOWERCASE,
+                self.lowercase,
+                self.policy.min_lowercase,
+            ),
+            ("numbers", DIGITS, self.numbers, self.policy.min_numbers),
+            ("symbols", symbols, self.symbols, self.policy.min_symbols),
+        ];
+
+        let mut enabled_classes = Vec::new();
+        for (name, chars, enabled, min) in classes {
+            if !enabled && min == 0 {
+                continue;
+            }
+            let chars: Vec<char> = chars
+                .chars()
+                .filter(|c| self.policy.is_allowed(*c))
+                .collect();
+            if chars.is_empty() {
+                if min != 0 {
+                    return Err(LprsError::Other(format!(
+                        "The policy needs {min} {name} but all of them are excluded"
+                    )));
+                }
+                continue;
+            }
+            enabled_classes.push((name, chars, min));
+        }
+        Ok(enabled_classes)
+    }
+
+    /// Generate a password, the policy minimums are placed first then the
+    /// password is shuffled
+    ///
+    /// ## Errors
+    /// - If there is no enabled kind of characters
+    /// - If the policy length is zero
+    /// - If the policy can't be satisfied
+    pub fn generate(&self) -> LprsResult<Generated> {
+        let classes = self.classes()?;
+        let pool: Vec<char> = classes
+            .iter()
+            .flat_map(|(_, chars, _)| chars.iter().copied())
+            .collect();
+        if pool.is_empty() {
+            return Err(LprsError::Other(
+                "You need to enable at least one kind of characters".to_owned(),
+            ));
+        }
+        let length = self.policy.length(self.length.get())?;
+        if length == 0 {
+            return Err(LprsError::Other(
+                "The password length can't be zero".to_owned(),
+            ));
+        }
+        let min_count: usize = classes.iter().map(|(_, _, min)| min).sum();
+        if min_count > length {
+            return Err(LprsError::Other(format!(
+                "The policy needs at least {min_count} characters, but the length is {length}"
+            )));
+        }
+
+        let mut rng = rand::rng();
+        let mut password: Vec<char> = classes
+            .iter()
+            .flat_map(|(_, chars, min)| std::iter::repeat(chars).take(*min))
+            .chain(std::iter::repeat(&pool).take(length - min_count))
+            .map(|chars| *chars.choose(&mut rng).expect("The chars are not empty"))
+            .collect();
+        password.shuffle(&mut rng);
+
+        Ok(Generated {
+            password: password.into_iter().collect(),
+            entropy:  length as f64 * (pool.len() as f64).log2(),
+        })
+    }
+}
+
+/// The generator specification, what to generate
+///
+/// Parsed from `LENGTH[:CLASSES]` for random characters, the classes are `u`
+/// (uppercase), `l` (lowercase), `n` (numbers) and `s` (symbols), all of them
+/// by default. Or from `words:N[:SEPARATOR]` for a passphrase of `N` words.
+/// Or from `pattern:TEMPLATE` for a password from a template, see
+/// [`pattern`]
+#[derive(Debug, Clone)]
+pub enum GeneratorSpec {
+    /// Random characters password
+    Chars(CharsGenerator),
+    /// Passphrase of random words
+    Passphrase(passphrase::PassphraseGenerator),
+    /// Password from a template
+    Pattern(pattern::PatternGenerator),
+}
+
+impl Default for GeneratorSpec {
+    fn default() -> Self {
+        Self::Chars(CharsGenerator {
+            length:    NonZeroUsize::new(DEFAULT_LENGTH).expect("Is not zero"),
+            uppercase: true,
+            lowercase: true,
+            numbers:   true,
+            symbols:   true,
+            policy:    PasswordPolicy::default(),
+        })
+    }
+}
+
+impl FromStr for GeneratorSpec {
+    type Err = LprsError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid_spec = || {
+            LprsError::ArgParse(format!(
+                "Invalid generator spec `{spec}`, the syntax is `LENGTH[:CLASSES]`, \
+                 `words:N[:SEPARATOR]` or `pattern:TEMPLATE`"
+            ))
+        };
+
+        if let Some(template) = spec.strip_prefix("pattern:") {
+            return template.parse().map(Self::Pattern);
+        }
+        if let Some(passphrase) = spec.strip_prefix("words:") {
+            let (words, separator) = passphrase.split_once(':').unwrap_or((passphrase, "-"));
+            return Ok(Self::Passphrase(passphrase::PassphraseGenerator {
+                words:          words.parse().map_err(|_| invalid_spec())?,
+                separator:      separator.to_owned(),
+                capitalization: passphrase::Capitalization::default(),
+                digits:         0,
+                symbols:        0,
+            }));
+        }
+
+        let (length, classes) = spec.split_once(':').unwrap_or((spec, "ulns"));
+        if classes.is_empty() || classes.chars().any(|c| !"ulns".contains(c)) {
+            return Err(invalid_spec());
+        }
+        Ok(Self::Chars(CharsGenerator {
+            length:    length.parse().map_err(|_| invalid_spec())?,
+            uppercase: classes.contains('u'),
+            lowercase: classes.contains('l'),
+            numbers:   classes.contains('n'),
+            symbols:   classes.contains('s'),
+            policy:    PasswordPolicy::default(),
+        }))
+    }
+}
+
+impl GeneratorSpec {
+    /// Set the password policy of the spec
+    ///
+    /// ## Errors
+    /// - If the spec is a passphrase or a pattern, the policy is for random
+    ///   characters
+    pub fn with_policy(self, policy: PasswordPolicy) -> LprsResult<Self> {
+        match self {
+            Self::Chars(generator) => {
+                Ok(Self::Chars(CharsGenerator {
+                    policy,
+                    ..generator
+                }))
+            }
+            Self::Passphrase(_) | Self::Pattern(_) if policy.is_empty() => Ok(self),
+            Self::Passphrase(_) => {
+                Err(LprsError::Other(
+                    "The password policy can't be used with a passphrase".to_owned(),
+                ))
+            }
+            Self::Pattern(_) => {
+                Err(LprsError::Other(
+                    "The password policy can't be used with a pattern".to_owned(),
+                ))
+            }
+        }
+    }
+
+    /// Generate a password, the passphrase needs the wordlist
+    ///
+    /// ## Errors
+    /// - If there is no wordlist for the passphrase, or it's invalid
+    /// - If there is no enabled kind of characters
+    pub fn generate(&self, wordlist: Option<&Path>) -> LprsResult<Generated> {
+        match self {
+            Self::Chars(generator) => generator.generate(),
+            Self::Pattern(generator) => Ok(generator.generate()),
+            Self::Passphrase(generator) => {
+                let wordlist = wordlist.ok_or_else(|| {
+                    LprsError::Other(
+                        "The passphrase needs a wordlist, pass it with the `--wordlist` option"
+                            .to_owned(),
+                    )
+                })?;
+                Ok(generator.generate(&passphrase::Wordlist::from_file(wordlist)?))
+            }
+        }
+    }
+}
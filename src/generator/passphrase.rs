@@ -0,0 +1,174 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The passphrase generator, random words from a diceware wordlist, e.g. the
+//! EFF large wordlist <https://www.eff.org/files/2016/07/18/eff_large_wordlist.txt>
+
+use std::{collections::BTreeSet, fs, num::NonZeroUsize, path::Path};
+
+use clap::ValueEnum;
+use rand::{
+    Rng,
+    seq::{IndexedMutRandom, IndexedRandom},
+};
+
+use super::{DIGITS, Generated, SYMBOLS};
+use crate::{LprsError, LprsResult};
+
+/// The capitalization of the passphrase words
+#[derive(Debug, Default, Clone, Copy, ValueEnum, Eq, PartialEq)]
+pub enum Capitalization {
+    /// Keep the words as they are in the wordlist
+    #[default]
+    None,
+    /// Capitalize the first letter of every word
+    First,
+    /// Uppercase all the words
+    Upper,
+    /// Capitalize the first letter of random words
+    Random,
+}
+
+/// A diceware wordlist
+#[derive(Debug)]
+pub struct Wordlist {
+    /// The unique words
+    words: Vec<String>,
+}
+
+impl Wordlist {
+    /// Parse the wordlist, a word per line. The diceware lines (`11111
+    /// abacus`) are supported, the dice numbers are ignored. The duplicated
+    /// words are removed
+    ///
+    /// ## Errors
+    /// - If the wordlist have less than two unique words
+    pub fn parse(wordlist: &str) -> LprsResult<Self> {
+        let words: BTreeSet<&str> = wordlist
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let first = parts.next()?;
+                match parts.next() {
+                    Some(word) if first.chars().all(|c| c.is_ascii_digit()) => Some(word),
+                    _ => Some(line.trim()),
+                }
+            })
+            .collect();
+        if words.len() < 2 {
+            return Err(LprsError::Other(
+                "The wordlist must have at least two unique words".to_owned(),
+            ));
+        }
+        Ok(Self {
+            words: words.into_iter().map(ToOwned::to_owned).collect(),
+        })
+    }
+
+    /// Read and parse the wordlist file, see [`Wordlist::parse`]
+    ///
+    /// ## Errors
+    /// - If the file can't be read
+    /// - If the wordlist is invalid
+    pub fn from_file(path: &Path) -> LprsResult<Self> {
+        log::debug!("Reading the wordlist: {}", path.display());
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Returns the number of the unique words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Always false, the wordlist have at least two words
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// The passphrase generator
+#[derive(Debug, Clone)]
+pub struct PassphraseGenerator {
+    /// The number of the words
+    pub words:          NonZeroUsize,
+    /// The words separator
+    pub separator:      String,
+    /// The words capitalization
+    pub capitalization: Capitalization,
+    /// The number of the random digits added to random words
+    pub digits:         usize,
+    /// The number of the random symbols added to random words
+    pub symbols:        usize,
+}
+
+impl PassphraseGenerator {
+    /// Generate a passphrase from the given wordlist.
+    ///
+    /// The entropy is a lower bound, it counts the words, the random
+    /// capitalization and the added characters but not their positions
+    pub fn generate(&self, wordlist: &Wordlist) -> Generated {
+        let mut rng = rand::rng();
+        let mut words: Vec<String> = (0..self.words.get())
+            .map(|_| {
+                let word = wordlist
+                    .words
+                    .choose(&mut rng)
+                    .expect("The wordlist is not empty");
+                match self.capitalization {
+                    Capitalization::None => word.clone(),
+                    Capitalization::First => capitalize(word),
+                    Capitalization::Upper => word.to_uppercase(),
+                    Capitalization::Random if rng.random_bool(0.5) => capitalize(word),
+                    Capitalization::Random => word.clone(),
+                }
+            })
+            .collect();
+
+        let digits: Vec<char> = DIGITS.chars().collect();
+        let symbols: Vec<char> = SYMBOLS.chars().collect();
+        for charset in [(&digits, self.digits), (&symbols, self.symbols)]
+            .into_iter()
+            .flat_map(|(charset, count)| std::iter::repeat(charset).take(count))
+        {
+            let word = words
+                .choose_mut(&mut rng)
+                .expect("There is at least one word");
+            word.push(*charset.choose(&mut rng).expect("The charset is not empty"));
+        }
+
+        let words_count = self.words.get() as f64;
+        let mut entropy = words_count * (wordlist.len() as f64).log2();
+        if self.capitalization == Capitalization::Random {
+            entropy += words_count;
+        }
+        entropy += self.digits as f64 * (digits.len() as f64).log2();
+        entropy += self.symbols as f64 * (symbols.len() as f64).log2();
+
+        Generated {
+            password: words.join(&self.separator),
+            entropy,
+        }
+    }
+}
+
+/// Capitalize the first letter of the word
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
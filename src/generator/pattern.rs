@@ -0,0 +1,173 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The pattern generator, a password from a template like `Cvcc-nnnn-Cvcc`
+//!
+//! The template characters:
+//! - `c` / `C`: A lowercase / uppercase consonant
+//! - `v` / `V`: A lowercase / uppercase vowel
+//! - `l` / `u`: A lowercase / uppercase letter
+//! - `n`: A number (0-9)
+//! - `s`: A symbol
+//! - `a`: A letter or a number
+//! - `x`: A letter, a number or a symbol
+//! - `\`: Escape the next character, it's used as it is
+//! - `{N}`: Repeat the previous character `N` times
+//!
+//! Any other character is used as it is.
+
+use std::{fmt, str::FromStr};
+
+use rand::seq::IndexedRandom;
+
+use super::{DIGITS, Generated, LOWERCASE, SYMBOLS, UPPERCASE};
+use crate::LprsError;
+
+/// The lowercase consonants
+pub const CONSONANTS: &str = "bcdfghjklmnpqrstvwxyz";
+/// The lowercase vowels
+pub const VOWELS: &str = "aeiou";
+/// The maximum length of the generated password
+pub const MAX_LENGTH: usize = 1024;
+
+/// A pattern element, a characters class or a literal character
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Element {
+    /// A random character from the class
+    Class(Vec<char>),
+    /// The character as it is
+    Literal(char),
+}
+
+impl Element {
+    /// Returns the class of the template character, or a literal
+    fn from_template(c: char) -> Self {
+        let class: String = match c {
+            'c' => CONSONANTS.to_owned(),
+            'C' => CONSONANTS.to_uppercase(),
+            'v' => VOWELS.to_owned(),
+            'V' => VOWELS.to_uppercase(),
+            'l' => LOWERCASE.to_owned(),
+            'u' => UPPERCASE.to_owned(),
+            'n' => DIGITS.to_owned(),
+            's' => SYMBOLS.to_owned(),
+            'a' => format!("{LOWERCASE}{UPPERCASE}{DIGITS}"),
+            'x' => format!("{LOWERCASE}{UPPERCASE}{DIGITS}{SYMBOLS}"),
+            _ => return Self::Literal(c),
+        };
+        Self::Class(class.chars().collect())
+    }
+}
+
+/// The pattern password generator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternGenerator {
+    /// The original template
+    template: String,
+    /// The template elements, a character for each one
+    elements: Vec<Element>,
+}
+
+impl FromStr for PatternGenerator {
+    type Err = LprsError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| LprsError::ArgParse(format!("Invalid pattern `{template}`: {reason}"));
+
+        let mut elements = Vec::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    elements.push(Element::Literal(
+                        chars
+                            .next()
+                            .ok_or_else(|| invalid("nothing to escape after `\\`"))?,
+                    ))
+                }
+                '{' => {
+                    let previous = elements
+                        .last()
+                        .cloned()
+                        .ok_or_else(|| invalid("nothing to repeat before `{`"))?;
+                    let invalid_repetition =
+                        || invalid("the repetition must be `{N}`, e.g. `n{4}`");
+                    let (count, _) = chars
+                        .as_str()
+                        .split_once('}')
+                        .ok_or_else(invalid_repetition)?;
+                    // Skip the count and the closing brace
+                    chars.nth(count.chars().count());
+                    let count: usize = count.parse().map_err(|_| invalid_repetition())?;
+                    if count == 0 {
+                        return Err(invalid("the repetition can't be zero"));
+                    }
+                    if count > MAX_LENGTH {
+                        return Err(invalid(&format!(
+                            "the password can't be longer than {MAX_LENGTH}"
+                        )));
+                    }
+                    elements.extend(std::iter::repeat(previous).take(count - 1));
+                }
+                _ => elements.push(Element::from_template(c)),
+            }
+            if elements.len() > MAX_LENGTH {
+                return Err(invalid(&format!(
+                    "the password can't be longer than {MAX_LENGTH}"
+                )));
+            }
+        }
+
+        if !elements.iter().any(|e| matches!(e, Element::Class(_))) {
+            return Err(invalid("there is no random character in it"));
+        }
+        Ok(Self {
+            template: template.to_owned(),
+            elements,
+        })
+    }
+}
+
+impl fmt::Display for PatternGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.template)
+    }
+}
+
+impl PatternGenerator {
+    /// Generate a password from the pattern. The entropy is the sum of the
+    /// random characters entropy, the literals don't add to it
+    pub fn generate(&self) -> Generated {
+        let mut rng = rand::rng();
+        let mut entropy = 0.0;
+        let password = self
+            .elements
+            .iter()
+            .map(|element| {
+                match element {
+                    Element::Class(chars) => {
+                        entropy += (chars.len() as f64).log2();
+                        *chars.choose(&mut rng).expect("The class is not empty")
+                    }
+                    Element::Literal(c) => *c,
+                }
+            })
+            .collect();
+
+        Generated { password, entropy }
+    }
+}
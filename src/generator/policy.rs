@@ -0,0 +1,135 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The password policy, the rules of a service passwords (e.g. "12 to 16
+//! characters, at least 1 digit and no `&` or `<`")
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::{LprsError, LprsResult};
+
+/// The ambiguous characters, looks similar to other characters
+pub const AMBIGUOUS: &str = "iloIO01|`'\"";
+
+/// The password policy rules
+#[derive(Debug, Default, Clone, Args, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PasswordPolicy {
+    /// The minimum password length, a shorter length is increased to it
+    #[arg(long, value_name = "N")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length:      Option<usize>,
+    /// The maximum password length, a longer length is decreased to it
+    #[arg(long, value_name = "N")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length:      Option<usize>,
+    /// The minimum number of uppercase letters, enables them
+    #[arg(long, value_name = "N", default_value_t)]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub min_uppercase:   usize,
+    /// The minimum number of lowercase letters, enables them
+    #[arg(long, value_name = "N", default_value_t)]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub min_lowercase:   usize,
+    /// The minimum number of numbers, enables them
+    #[arg(long, value_name = "N", default_value_t)]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub min_numbers:     usize,
+    /// The minimum number of symbols, enables them
+    #[arg(long, value_name = "N", default_value_t)]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub min_symbols:     usize,
+    /// The allowed symbols, replaces the default symbols
+    #[arg(long, value_name = "CHARS")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_symbols: Option<String>,
+    /// The excluded characters, never used in the password
+    #[arg(long, value_name = "CHARS")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude:         Option<String>,
+    /// Avoid the ambiguous characters, e.g. `l`, `1`, `O` and `0`
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub avoid_ambiguous: bool,
+}
+
+/// Returns true if the number is zero, used to skip the zero minimums
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_zero(number: &usize) -> bool {
+    *number == 0
+}
+
+impl PasswordPolicy {
+    /// Returns true if there is no rules
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns the given length clamped to the policy length
+    ///
+    /// ## Errors
+    /// - If the minimum length is greater than the maximum length
+    pub fn length(&self, length: usize) -> LprsResult<usize> {
+        let min_length = self.min_length.unwrap_or(0);
+        let max_length = self.max_length.unwrap_or(usize::MAX);
+        if min_length > max_length {
+            return Err(LprsError::Other(format!(
+                "The policy minimum length {min_length} is greater than its maximum length \
+                 {max_length}"
+            )));
+        }
+        Ok(length.clamp(min_length, max_length))
+    }
+
+    /// Returns true if the character is allowed by the exclusions
+    pub fn is_allowed(&self, c: char) -> bool {
+        !(self.exclude.as_ref().is_some_and(|e| e.contains(c))
+            || (self.avoid_ambiguous && AMBIGUOUS.contains(c)))
+    }
+}
+
+impl std::fmt::Display for PasswordPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut rules = Vec::new();
+        match (self.min_length, self.max_length) {
+            (Some(min), Some(max)) => rules.push(format!("length: {min}-{max}")),
+            (Some(min), None) => rules.push(format!("min length: {min}")),
+            (None, Some(max)) => rules.push(format!("max length: {max}")),
+            (None, None) => {}
+        }
+        for (class, min) in [
+            ("uppercase", self.min_uppercase),
+            ("lowercase", self.min_lowercase),
+            ("numbers", self.min_numbers),
+            ("symbols", self.min_symbols),
+        ] {
+            if min != 0 {
+                rules.push(format!("min {class}: {min}"));
+            }
+        }
+        if let Some(ref symbols) = self.allowed_symbols {
+            rules.push(format!("allowed symbols: {symbols}"));
+        }
+        if let Some(ref exclude) = self.exclude {
+            rules.push(format!("excluded: {exclude}"));
+        }
+        if self.avoid_ambiguous {
+            rules.push("avoid ambiguous".to_owned());
+        }
+        write!(f, "{}", rules.join(", "))
+    }
+}
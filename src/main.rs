@@ -19,15 +19,20 @@
 use std::env;
 use std::process::ExitCode;
 
-use clap::Parser;
 use inquire::InquireError;
 
 /// A set of clap vaule parsers used to parse some CLI arguments
 pub mod clap_parsers;
 /// The main module of the lprs crate, contains the cli and the commands.
 pub mod cli;
+/// The config module, contains the profiles and the commands defaults.
+pub mod config;
 /// The errors module, contains the errors and the result type.
 pub mod errors;
+/// The generator module, contains the password and passphrase generators.
+pub mod generator;
+/// The TUI module, contains the full-screen terminal UI.
+pub mod tui;
 /// The utils module, contains the utility functions of all the modules.
 pub mod utils;
 /// The vault module, contains the vault struct and the vaults manager.
@@ -47,7 +52,13 @@ pub const DEFAULT_VAULTS_FILE: &str = "vaults.lprs";
 const RESERVED_FIELD_PREFIX: &str = ".lprsfield.";
 
 fn main() -> ExitCode {
-    let lprs_cli = cli::Cli::parse();
+    let lprs_cli = match cli::Cli::parse_with_config() {
+        Ok(lprs_cli) => lprs_cli,
+        Err(err) => {
+            eprintln!("{err}");
+            return err.exit_code();
+        }
+    };
     if lprs_cli.verbose {
         env::set_var("RUST_LOG", "lprs");
     }
@@ -0,0 +1,241 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::collections::BTreeMap;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    LprsError,
+    LprsResult,
+    config::policies::PoliciesConfig,
+    generator::GeneratorSpec,
+    vault::{Vault, cipher, otpauth, strength::StrengthPolicy},
+};
+
+/// The form fields labels, in order
+pub const FIELDS: [&str; 7] = [
+    "Name",
+    "Username",
+    "Password",
+    "Service",
+    "Note",
+    "Folder",
+    "TOTP Secret",
+];
+/// The name field index
+const NAME: usize = 0;
+/// The username field index
+const USERNAME: usize = 1;
+/// The password field index
+pub const PASSWORD: usize = 2;
+/// The service field index
+const SERVICE: usize = 3;
+/// The note field index
+const NOTE: usize = 4;
+/// The folder field index
+const FOLDER: usize = 5;
+/// The TOTP secret field index
+const TOTP_SECRET: usize = 6;
+
+/// What to do after a form key press
+pub enum FormAction {
+    /// Keep editing the form
+    Continue,
+    /// Close the form without saving
+    Cancel,
+    /// Save the form
+    Submit,
+}
+
+/// The vault add and edit form, the empty fields are removed from the vault
+pub struct VaultForm {
+    /// The edited vault index, `None` when adding a new vault
+    editing:           Option<usize>,
+    /// The fields values, in the [`FIELDS`] order
+    pub values:        [String; FIELDS.len()],
+    /// The focused field index
+    pub focused:       usize,
+    /// Whether to show the password
+    pub show_password: bool,
+    /// The last save error
+    pub error:         Option<String>,
+    /// The weak password that the user was warned about, saving it again
+    /// accepts it
+    weak_password:     Option<String>,
+}
+
+impl VaultForm {
+    /// Create an empty form to add a new vault
+    pub fn add() -> Self {
+        Self {
+            editing:       None,
+            values:        Default::default(),
+            focused:       0,
+            show_password: false,
+            error:         None,
+            weak_password: None,
+        }
+    }
+
+    /// Create a form to edit the vault, with its current values
+    pub fn edit(idx: usize, vault: &Vault) -> Self {
+        let value = |value: &Option<String>| value.clone().unwrap_or_default();
+        Self {
+            editing: Some(idx),
+            values: [
+                vault.name.clone(),
+                value(&vault.username),
+                value(&vault.password),
+                value(&vault.service),
+                value(&vault.note),
+                value(&vault.folder),
+                value(&vault.totp_secret),
+            ],
+            ..Self::add()
+        }
+    }
+
+    /// Returns true if it's editing an existing vault
+    pub const fn is_edit(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    /// Returns the field value, `None` if it's empty
+    fn value(&self, idx: usize) -> Option<String> {
+        (!self.values[idx].is_empty()).then(|| self.values[idx].clone())
+    }
+
+    /// Generate the password, honouring the password policy of the vault
+    /// service domain
+    fn generate_password(&mut self) -> LprsResult<()> {
+        let mut spec = GeneratorSpec::default();
+        let policies = PoliciesConfig::load()?;
+        let vault = Vault::new(
+            "",
+            None::<String>,
+            None::<String>,
+            self.value(SERVICE),
+            None::<String>,
+            BTreeMap::new(),
+            None::<String>,
+            cipher::TotpHash::default(),
+            otpauth::DEFAULT_DIGITS,
+        );
+        if let Some(policy) = policies.vault_policy(&vault) {
+            spec = spec.with_policy(policy.clone())?;
+        }
+        self.values[PASSWORD] = spec.generate(None)?.password;
+        Ok(())
+    }
+
+    /// Handle the key press
+    pub fn handle_key(&mut self, key: KeyEvent) -> FormAction {
+        let value = &mut self.values[self.focused];
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('s') => return FormAction::Submit,
+                KeyCode::Char('r') => self.show_password = !self.show_password,
+                KeyCode::Char('g') => {
+                    if let Err(err) = self.generate_password() {
+                        self.error = Some(err.to_string());
+                    }
+                }
+                KeyCode::Char('u') => value.clear(),
+                _ => {}
+            }
+            return FormAction::Continue;
+        }
+        match key.code {
+            KeyCode::Esc => return FormAction::Cancel,
+            KeyCode::Enter if self.focused == FIELDS.len() - 1 => return FormAction::Submit,
+            KeyCode::Enter | KeyCode::Tab | KeyCode::Down => {
+                self.focused = (self.focused + 1) % FIELDS.len();
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focused = self.focused.checked_sub(1).unwrap_or(FIELDS.len() - 1);
+            }
+            KeyCode::Backspace => {
+                value.pop();
+            }
+            KeyCode::Char(c) => value.push(c),
+            _ => {}
+        }
+        FormAction::Continue
+    }
+
+    /// Validate the form then add or edit the vault, returns the vault name
+    ///
+    /// ## Errors
+    /// - If the name is empty
+    /// - If the TOTP secret is invalid base32
+    /// - If the changed password is weak, the first time it's saved
+    pub fn save(
+        &mut self,
+        vaults: &mut Vec<Vault>,
+        strength: &StrengthPolicy,
+    ) -> LprsResult<String> {
+        let name = self.values[NAME].trim().to_owned();
+        if name.is_empty() {
+            return Err(LprsError::EmptyValue);
+        }
+        let totp_secret = self.value(TOTP_SECRET);
+        if let Some(ref totp_secret) = totp_secret {
+            cipher::base32_decode(totp_secret).map_err(|_| {
+                LprsError::Base32("Invalid TOTP secret, must be valid base32 string".to_owned())
+            })?;
+        }
+        if let Some(password) = self.value(PASSWORD) {
+            let is_changed = self
+                .editing
+                .map_or(true, |idx| vaults[idx].password.as_ref() != Some(&password));
+            if is_changed && self.weak_password.as_ref() != Some(&password) {
+                if let Some(weakness) = strength.weakness(&password) {
+                    self.weak_password = Some(password);
+                    return Err(LprsError::WeakPassword(format!(
+                        "{weakness}, press Ctrl-S again to use it anyway"
+                    )));
+                }
+            }
+        }
+
+        let vault = match self.editing {
+            Some(idx) => &mut vaults[idx],
+            None => {
+                vaults.push(Vault::new(
+                    "",
+                    None::<String>,
+                    None::<String>,
+                    None::<String>,
+                    None::<String>,
+                    BTreeMap::new(),
+                    None::<String>,
+                    cipher::TotpHash::default(),
+                    otpauth::DEFAULT_DIGITS,
+                ));
+                vaults.last_mut().expect("The vault is just pushed")
+            }
+        };
+        vault.name.clone_from(&name);
+        vault.username = self.value(USERNAME);
+        vault.set_password(self.value(PASSWORD));
+        vault.service = self.value(SERVICE);
+        vault.note = self.value(NOTE);
+        vault.folder = self.value(FOLDER);
+        vault.totp_secret = totp_secret;
+        Ok(name)
+    }
+}
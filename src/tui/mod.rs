@@ -0,0 +1,290 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The full-screen terminal UI, a searchable vaults list with the vault
+//! details, and the add, edit and delete forms
+
+use std::time::Duration;
+
+use ratatui::{
+    DefaultTerminal,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    widgets::ListState,
+};
+
+use self::form::VaultForm;
+use crate::{
+    LprsResult,
+    utils,
+    vault::{Vault, Vaults, cipher, strength::StrengthPolicy},
+};
+
+/// The vault add and edit form
+mod form;
+/// The TUI drawing
+mod ui;
+
+/// How often the TUI is redrawn without input, for the TOTP countdown
+const TICK: Duration = Duration::from_millis(250);
+
+/// The TUI mode, what the keys do
+enum Mode {
+    /// Browsing the vaults list
+    Browse,
+    /// Typing the search text
+    Search,
+    /// Adding or editing a vault
+    Form(Box<VaultForm>),
+    /// Confirming the deletion of the vault at the index
+    ConfirmDelete(usize),
+}
+
+/// The TUI state
+struct App {
+    /// The vaults manager, the changes are exported right away
+    vaults:       Vaults,
+    /// The search text, filters the vaults list
+    search:       String,
+    /// The indexes of the vaults that match the search
+    filtered:     Vec<usize>,
+    /// The vaults list state, the selected vault
+    list_state:   ListState,
+    /// Whether to show the secrets in the details
+    show_secrets: bool,
+    /// The current mode
+    mode:         Mode,
+    /// The status message, shown in the footer
+    status:       Option<String>,
+    /// Whether to quit the TUI
+    quit:         bool,
+    /// The minimum strength of the saved passwords
+    strength:     StrengthPolicy,
+}
+
+/// Run the TUI until the user quits it
+///
+/// ## Errors
+/// - If the terminal can't be drawn or its events can't be read
+pub fn run(vaults: Vaults, strength: StrengthPolicy) -> LprsResult<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new(vaults, strength).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    /// Create the app state
+    fn new(vaults: Vaults, strength: StrengthPolicy) -> Self {
+        let mut app = Self {
+            vaults,
+            search: String::new(),
+            filtered: Vec::new(),
+            list_state: ListState::default(),
+            show_secrets: false,
+            mode: Mode::Browse,
+            status: None,
+            quit: false,
+            strength,
+        };
+        app.apply_search();
+        app
+    }
+
+    /// Draw and handle the events until the user quits
+    fn run(mut self, terminal: &mut DefaultTerminal) -> LprsResult<()> {
+        while !self.quit {
+            terminal.draw(|frame| ui::draw(frame, &mut self))?;
+            // Handle all the pending events before the next draw, e.g. a paste
+            let mut timeout = TICK;
+            while !self.quit && event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        self.handle_key(key);
+                    }
+                }
+                timeout = Duration::ZERO;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the selected vault index, in the vaults
+    fn selected(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|idx| self.filtered.get(idx).copied())
+    }
+
+    /// Returns the selected vault
+    fn selected_vault(&self) -> Option<&Vault> {
+        self.selected().map(|idx| &self.vaults.vaults[idx])
+    }
+
+    /// Filter the vaults by the search text, like the `list` command filter
+    fn apply_search(&mut self) {
+        let search = self.search.to_lowercase();
+        let matches = |value: &Option<String>| {
+            value
+                .as_deref()
+                .is_some_and(|v| v.to_lowercase().contains(&search))
+        };
+        self.filtered = self
+            .vaults
+            .vaults
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| {
+                v.name.to_lowercase().contains(&search)
+                    || matches(&v.username)
+                    || matches(&v.service)
+                    || matches(&v.note)
+                    || matches(&v.folder)
+                    || v.public_custom_fields()
+                        .any(|value| value.to_lowercase().contains(&search))
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let selected = self.list_state.selected().unwrap_or_default();
+        self.list_state
+            .select((!self.filtered.is_empty()).then(|| selected.min(self.filtered.len() - 1)));
+    }
+
+    /// Export the vaults, shows the error in the status if it failed
+    fn export(&mut self, success_message: String) {
+        self.status = Some(match self.vaults.try_export() {
+            Ok(()) => success_message,
+            Err(err) => format!("Error: {err}"),
+        });
+        self.apply_search();
+    }
+
+    /// Copy a field of the selected vault to the clipboard
+    fn copy(&mut self, field: &str) {
+        let Some(vault) = self.selected_vault() else {
+            return;
+        };
+        let value = match field {
+            "username" => vault.username.clone(),
+            "password" => vault.password.clone(),
+            _ => {
+                vault.totp_secret.as_ref().and_then(|secret| {
+                    cipher::totp_now(secret, &vault.totp_hash, vault.totp_digits)
+                        .ok()
+                        .map(|(code, _)| code)
+                })
+            }
+        };
+        self.status = Some(match value {
+            Some(value) => {
+                match utils::copy_to_clipboard(&value) {
+                    Ok(()) => format!("Copied the {field} to the clipboard"),
+                    Err(err) => format!("Error: {err}"),
+                }
+            }
+            None => format!("There is no {field} to copy"),
+        });
+    }
+
+    /// Handle the key press in the current mode
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.handle_browse_key(key),
+            Mode::Search => self.handle_search_key(key),
+            Mode::Form(form) => self.handle_form_key(form, key),
+            Mode::ConfirmDelete(idx) => {
+                if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                    let vault = self.vaults.vaults.remove(idx);
+                    self.export(format!("Deleted the `{}` vault", vault.name));
+                } else {
+                    self.status = Some("Canceled".to_owned());
+                }
+            }
+        }
+    }
+
+    /// Handle the key press while browsing the vaults
+    fn handle_browse_key(&mut self, key: KeyEvent) {
+        self.status = None;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.list_state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list_state.select_previous(),
+            KeyCode::Home | KeyCode::Char('g') => self.list_state.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.list_state.select_last(),
+            KeyCode::Char('/') => self.mode = Mode::Search,
+            KeyCode::Char('r') => self.show_secrets = !self.show_secrets,
+            KeyCode::Char('u') => self.copy("username"),
+            KeyCode::Char('p') => self.copy("password"),
+            KeyCode::Char('t') => self.copy("TOTP code"),
+            KeyCode::Char('a') => self.mode = Mode::Form(Box::new(VaultForm::add())),
+            KeyCode::Char('e') => {
+                if let Some(idx) = self.selected() {
+                    self.mode =
+                        Mode::Form(Box::new(VaultForm::edit(idx, &self.vaults.vaults[idx])));
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(idx) = self.selected() {
+                    self.mode = Mode::ConfirmDelete(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle the key press while typing the search text
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.search.clear(),
+            KeyCode::Enter => {}
+            KeyCode::Backspace => {
+                self.search.pop();
+                self.mode = Mode::Search;
+            }
+            KeyCode::Char(c) => {
+                self.search.push(c);
+                self.mode = Mode::Search;
+            }
+            _ => self.mode = Mode::Search,
+        }
+        self.apply_search();
+    }
+
+    /// Handle the key press in the form, saves the vault when it's submitted
+    fn handle_form_key(&mut self, mut form: Box<VaultForm>, key: KeyEvent) {
+        match form.handle_key(key) {
+            form::FormAction::Continue => self.mode = Mode::Form(form),
+            form::FormAction::Cancel => self.status = Some("Canceled".to_owned()),
+            form::FormAction::Submit => {
+                match form.save(&mut self.vaults.vaults, &self.strength) {
+                    Ok(name) => {
+                        let action = if form.is_edit() { "Edited" } else { "Added" };
+                        self.export(format!("{action} the `{name}` vault"));
+                    }
+                    Err(err) => {
+                        form.error = Some(err.to_string());
+                        self.mode = Mode::Form(form);
+                    }
+                }
+            }
+        }
+    }
+}
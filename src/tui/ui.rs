@@ -0,0 +1,321 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Wrap},
+};
+
+use super::{
+    App,
+    Mode,
+    form::{self, VaultForm},
+};
+use crate::vault::{MASK, Vault, cipher, strength::Strength};
+
+/// The TOTP period in seconds
+const TOTP_PERIOD: u8 = 30;
+
+/// Draw the TUI
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [search_area, main_area, footer_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [list_area, details_area] =
+        Layout::horizontal([Constraint::Percentage(35), Constraint::Fill(1)]).areas(main_area);
+
+    draw_search(frame, app, search_area);
+    draw_list(frame, app, list_area);
+    draw_details(frame, app, details_area);
+    draw_footer(frame, app, footer_area);
+    if let Mode::Form(ref form) = app.mode {
+        draw_form(frame, form, main_area);
+    }
+}
+
+/// Returns the block style, highlighted if it's focused
+fn block(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    if focused {
+        block.border_style(Style::default().fg(Color::Yellow))
+    } else {
+        block
+    }
+}
+
+/// Draw the search bar
+fn draw_search(frame: &mut Frame, app: &App, area: Rect) {
+    let searching = matches!(app.mode, Mode::Search);
+    frame.render_widget(
+        Paragraph::new(format!("/{}", app.search)).block(block("Search", searching)),
+        area,
+    );
+    if searching {
+        frame.set_cursor_position(Position::new(
+            area.x + 2 + app.search.chars().count() as u16,
+            area.y + 1,
+        ));
+    }
+}
+
+/// Draw the vaults list
+fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .filtered
+        .iter()
+        .map(|idx| {
+            ListItem::new(format!(
+                "{}) {}",
+                idx + 1,
+                app.vaults.vaults[*idx].list_name()
+            ))
+        })
+        .collect();
+    let title = format!(
+        "Vaults ({}/{})",
+        app.filtered.len(),
+        app.vaults.vaults.len()
+    );
+    let list = List::new(items)
+        .block(block(&title, matches!(app.mode, Mode::Browse)))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+/// Returns the details lines of the vault, the secrets are masked unless
+/// `show_secrets` is true
+fn details_lines(vault: &Vault, show_secrets: bool) -> Vec<Line<'_>> {
+    let secret = |value: &str| {
+        if show_secrets {
+            value.to_owned()
+        } else {
+            MASK.to_owned()
+        }
+    };
+    let field = |label: &str, value: String| {
+        Line::from(vec![
+            Span::from(format!("{label}: ")).bold(),
+            Span::from(value),
+        ])
+    };
+
+    let mut lines = vec![field("Name", vault.name.clone())];
+    if !vault.item.is_login() {
+        lines.push(field("Type", vault.item.item_type().label().to_owned()));
+    }
+    if let Some(ref username) = vault.username {
+        lines.push(field("Username", username.clone()));
+    }
+    if let Some(ref password) = vault.password {
+        lines.push(field("Password", secret(password)));
+        lines.push(field("Strength", Strength::of(password).to_string()));
+    }
+    if let Some(ref service) = vault.service {
+        lines.push(field("Service", service.clone()));
+    }
+    if let Some(ref folder) = vault.folder {
+        lines.push(field("Folder", folder.clone()));
+    }
+    if vault.totp_secret.is_some() {
+        lines.push(field(
+            "TOTP Secret",
+            secret(vault.totp_secret.as_deref().unwrap_or_default()),
+        ));
+    }
+    for (label, value, is_secret) in vault.item.display_fields() {
+        let value = if is_secret { secret(&value) } else { value };
+        if value.contains('\n') {
+            lines.push(Line::from(format!("{label}:")).bold());
+            lines.extend(value.lines().map(|line| Line::from(line.to_owned())));
+        } else {
+            lines.push(field(label, value));
+        }
+    }
+    for (key, value) in &vault.custom_fields {
+        let kind = vault.field_kind(key);
+        let value = if kind.is_secret() {
+            secret(value)
+        } else {
+            value.clone()
+        };
+        if value.contains('\n') {
+            lines.push(Line::from(format!("{key}:")).bold());
+            lines.extend(value.lines().map(|line| Line::from(line.to_owned())));
+        } else {
+            lines.push(field(key, value));
+        }
+    }
+    for (key, code, _) in vault.custom_totp_codes().unwrap_or_default() {
+        lines.push(field(&format!("{key} Code"), code));
+    }
+    if let Some(ref note) = vault.note {
+        lines.push(Line::from("Note:").bold());
+        lines.extend(note.lines().map(|line| Line::from(line.to_owned())));
+    }
+    lines
+}
+
+/// Draw the selected vault details, with its TOTP code countdown
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+    let details = block("Details", false);
+    let Some(vault) = app.selected_vault() else {
+        frame.render_widget(Paragraph::new("No vault selected").block(details), area);
+        return;
+    };
+
+    let totp = vault
+        .totp_secret
+        .as_ref()
+        .map(|secret| cipher::totp_now(secret, &vault.totp_hash, vault.totp_digits));
+    let [fields_area, totp_area] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(if totp.is_some() { 3 } else { 0 }),
+    ])
+    .areas(area);
+
+    frame.render_widget(
+        Paragraph::new(details_lines(vault, app.show_secrets))
+            .block(details)
+            .wrap(Wrap { trim: false }),
+        fields_area,
+    );
+    match totp {
+        Some(Ok((code, remaining))) => {
+            frame.render_widget(
+                Gauge::default()
+                    .block(block("TOTP Code", false))
+                    .gauge_style(Style::default().fg(if remaining <= 5 {
+                        Color::Red
+                    } else {
+                        Color::Green
+                    }))
+                    .ratio(f64::from(remaining) / f64::from(TOTP_PERIOD))
+                    .label(format!("{code} ({remaining}s)")),
+                totp_area,
+            );
+        }
+        Some(Err(err)) => {
+            frame.render_widget(
+                Paragraph::new(err.to_string()).block(block("TOTP Code", false)),
+                totp_area,
+            );
+        }
+        None => {}
+    }
+}
+
+/// Draw the footer, the status message or the keys help
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let footer = match app.mode {
+        Mode::ConfirmDelete(idx) => {
+            Line::from(format!(
+                "Delete the `{}` vault? (y/n)",
+                app.vaults.vaults[idx].name
+            ))
+            .red()
+        }
+        Mode::Search => Line::from("Type to search, Enter: done, Esc: clear"),
+        Mode::Form(_) => {
+            Line::from(
+                "Tab/Shift-Tab: next/previous field, Ctrl-G: generate password, Ctrl-R: reveal \
+                 password, Ctrl-U: clear field, Ctrl-S: save, Esc: cancel",
+            )
+        }
+        Mode::Browse => {
+            app.status.as_deref().map_or_else(
+                || {
+                    Line::from(
+                        "q: quit, /: search, a: add, e: edit, d: delete, r: reveal secrets, \
+                         u/p/t: copy username/password/TOTP code",
+                    )
+                },
+                |status| Line::from(status.to_owned()).yellow(),
+            )
+        }
+    };
+    frame.render_widget(Paragraph::new(footer), area);
+}
+
+/// Draw the add or edit form, in the middle of the area
+fn draw_form(frame: &mut Frame, form: &VaultForm, area: Rect) {
+    let label_width = form::FIELDS
+        .iter()
+        .map(|l| l.len())
+        .max()
+        .unwrap_or_default()
+        + 2;
+    let [_, form_area, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(form::FIELDS.len() as u16 + 4),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    let [_, form_area, _] = Layout::horizontal([
+        Constraint::Percentage(15),
+        Constraint::Percentage(70),
+        Constraint::Percentage(15),
+    ])
+    .areas(form_area);
+
+    let mut lines: Vec<Line> = form
+        .values
+        .iter()
+        .zip(form::FIELDS)
+        .enumerate()
+        .map(|(idx, (value, label))| {
+            let value = if idx == form::PASSWORD && !form.show_password {
+                "*".repeat(value.chars().count())
+            } else {
+                value.replace('\n', "⏎")
+            };
+            let label = Span::from(format!("{:label_width$}", format!("{label}:")));
+            if idx == form.focused {
+                Line::from(vec![label.yellow().bold(), Span::from(value)])
+            } else {
+                Line::from(vec![label, Span::from(value)])
+            }
+        })
+        .collect();
+    lines.push(Line::default());
+    if let Some(ref error) = form.error {
+        lines.push(Line::from(error.clone()).red());
+    } else if !form.values[form::PASSWORD].is_empty() {
+        lines.push(Line::from(format!(
+            "Password strength: {}",
+            Strength::of(&form.values[form::PASSWORD])
+        )));
+    }
+
+    let title = if form.is_edit() {
+        "Edit vault"
+    } else {
+        "Add vault"
+    };
+    frame.render_widget(Clear, form_area);
+    frame.render_widget(Paragraph::new(lines).block(block(title, true)), form_area);
+
+    let focused_len = form.values[form.focused].chars().count();
+    frame.set_cursor_position(Position::new(
+        form_area.x + 1 + (label_width + focused_len) as u16,
+        form_area.y + 1 + form.focused as u16,
+    ));
+}
@@ -14,9 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
 
-use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
 use std::num::NonZeroUsize;
-use std::{fs, path::PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use either::Either;
 use inquire::{
@@ -27,7 +32,7 @@ use inquire::{
 use passwords::{analyzer, scorer};
 use sha2::Digest;
 
-use crate::vault::Vault;
+use crate::vault::{Vault, custom_field::FieldKind};
 use crate::{LprsError, LprsResult};
 
 /// Returns the local project dir joined with the given file name
@@ -49,11 +54,33 @@ pub fn local_project_file(filename: &str) -> LprsResult<PathBuf> {
     Ok(local_dir.join(filename))
 }
 
+/// Returns the project config dir joined with the given file name
+///
+/// ## Errors
+/// - If the project dir can't be extracted from the OS
+/// - If the project config dir can't be created
+pub fn config_project_file(filename: &str) -> LprsResult<PathBuf> {
+    let config_dir = directories::ProjectDirs::from("", "", "lprs")
+        .map(|d| d.config_dir().to_path_buf())
+        .ok_or_else(|| {
+            LprsError::ProjectDir("Can't extract the project_dir from this OS".to_owned())
+        })?;
+    log::debug!("Project config dir: {:?}", config_dir.display());
+    if !config_dir.exists() {
+        log::info!(
+            "Creating the project config dir: {:?}",
+            config_dir.display()
+        );
+        fs::create_dir_all(&config_dir)?;
+    }
+    Ok(config_dir.join(filename))
+}
+
 /// Ask the user for a secret in the stdin
 ///
 /// ## Errors
 /// - If can't read the user input
-fn secret_prompt(
+pub fn secret_prompt(
     prompt_message: &str,
     confirmation: bool,
     validators: Option<Vec<Box<dyn StringValidator>>>,
@@ -93,6 +120,45 @@ pub fn user_secret(
     })
 }
 
+/// Returns the vault note as it is, read from the stdin if it's `-`, or from
+/// the note file if there is one
+///
+/// ## Errors
+/// - If can't read the note from the stdin or the file
+/// - If the note is not valid UTF-8
+pub fn read_note(note: Option<String>, note_file: Option<&Path>) -> LprsResult<Option<String>> {
+    let note = match (note, note_file) {
+        (_, Some(path)) => {
+            log::info!("Reading the note from: {}", path.display());
+            String::from_utf8(fs::read(path)?)?
+        }
+        (Some(note), None) if note == "-" => {
+            log::info!("Reading the note from the stdin");
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf)?;
+            String::from_utf8(buf)?
+        }
+        (note, None) => return Ok(note),
+    };
+    Ok(Some(note))
+}
+
+/// Create a new file readable and writable only by the user, for the files
+/// that have secrets
+///
+/// ## Errors
+/// - If the file already exists or can't be created
+pub fn create_private_file(path: &Path) -> LprsResult<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path).map_err(LprsError::from)
+}
+
 /// Returns the default vaults json file
 ///
 /// ## Errors
@@ -126,6 +192,14 @@ pub fn password_validator(password: &str) -> Result<Validation, inquire::CustomU
     })
 }
 
+/// Returns the current time, seconds since the Unix epoch
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("SystemTime before UNIX EPOCH!")
+        .as_secs()
+}
+
 /// Ask the user for the master password, then returns it
 ///
 /// ## Errors
@@ -151,18 +225,17 @@ pub fn get_duplicated_field(fields: &[(String, Option<String>)]) -> Option<&str>
     })
 }
 
-/// Apply the edited fields to the vault fields.
+/// Apply the edited fields to the vault custom fields.
 /// This will:
 /// - Add the field if it's not in the fields map.
 /// - Update the field if it's in the map.
-/// - Remove the field if its value is empty string.
-pub fn apply_custom_fields(
-    fields: &mut BTreeMap<String, String>,
-    edited_fields: Vec<(String, String)>,
-) {
+/// - Remove the field with its kind if its value is empty string.
+pub fn apply_custom_fields(vault: &mut Vault, edited_fields: Vec<(String, String)>) {
+    let fields = &mut vault.custom_fields;
     for (key, value) in edited_fields {
         if fields.contains_key(&key) && value.is_empty() {
             fields.remove(&key);
+            vault.custom_field_kinds.remove(&key);
         } else {
             // The field is not there or its value not empty,
             // so add it or update its value
@@ -172,19 +245,23 @@ pub fn apply_custom_fields(
 }
 
 /// Make sure all custom field values are there, if not, ask the user for it
+/// as its kind, see [`FieldKind::prompt`]. The multi-line values `-` are read
+/// from the stdin
 ///
 /// ## Errors
 /// - If can't read the user input
 pub fn prompt_custom(
     custom_fields: Vec<(String, Option<String>)>,
+    field_kind: impl Fn(&str) -> FieldKind,
 ) -> LprsResult<Vec<(String, String)>> {
     let mut new_fields = Vec::new();
 
     for (key, value) in custom_fields {
+        let kind = field_kind(&key);
         if let Some(value) = value {
-            new_fields.push((key, value));
+            new_fields.push((key, kind.read_value(value)?));
         } else {
-            let value = secret_prompt(&format!("Value of `{key}`:"), false, None)?;
+            let value = kind.prompt(&key)?;
             new_fields.push((key, value));
         }
     }
@@ -224,3 +301,50 @@ pub fn vault_by_index_or_name<'v>(
         })?,
     ))
 }
+
+/// Copy the text to the clipboard, using the first available clipboard
+/// command (`wl-copy`, `xclip`, `xsel`, `pbcopy` or `clip.exe`)
+///
+/// ## Errors
+/// - If there is no available clipboard command
+/// - If the clipboard command failed
+pub fn copy_to_clipboard(text: &str) -> LprsResult<()> {
+    /// The clipboard commands with their arguments
+    const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+        &["pbcopy"],
+        &["clip.exe"],
+    ];
+
+    for command in CLIPBOARD_COMMANDS {
+        let Ok(mut child) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        log::debug!("Copying to the clipboard with `{}`", command[0]);
+        child
+            .stdin
+            .take()
+            .expect("The stdin is piped")
+            .write_all(text.as_bytes())?;
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    let commands = CLIPBOARD_COMMANDS
+        .iter()
+        .map(|command| format!("`{}`", command[0]))
+        .collect::<Vec<_>>();
+    Err(LprsError::Other(format!(
+        "Can't copy to the clipboard, none of the clipboard commands worked ({}), install one of \
+         them",
+        commands.join(", ")
+    )))
+}
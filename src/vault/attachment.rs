@@ -0,0 +1,197 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The vaults attachments, files stored inside the vault and encrypted with it
+
+use std::{collections::HashSet, fmt, path::Path};
+
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Vault;
+use crate::{LprsError, LprsResult};
+
+/// The maximum size of a single attachment, 10 MiB
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+/// The maximum size of all the attachments of a vault, 25 MiB
+pub const MAX_VAULT_ATTACHMENTS_SIZE: usize = 25 * 1024 * 1024;
+/// The MIME type of the unknown files
+pub const DEFAULT_MIME: &str = "application/octet-stream";
+
+/// The MIME types of the known file extensions
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("json", "application/json"),
+    ("toml", "application/toml"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("pem", "application/x-pem-file"),
+    ("crt", "application/x-x509-ca-cert"),
+    ("cer", "application/pkix-cert"),
+    ("der", "application/x-x509-ca-cert"),
+    ("p12", "application/x-pkcs12"),
+    ("pfx", "application/x-pkcs12"),
+    ("pub", "text/plain"),
+    ("asc", "application/pgp-keys"),
+    ("gpg", "application/pgp-encrypted"),
+    ("kdbx", "application/x-keepass2"),
+];
+
+/// A file attached to a vault
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Attachment {
+    /// The attachment name, unique in its vault
+    pub name: String,
+    /// The attachment MIME type, e.g. `application/pdf`
+    pub mime: String,
+    /// The attachment content, base64 encoded in the JSON
+    #[serde(
+        serialize_with = "serialize_data",
+        deserialize_with = "deserialize_data"
+    )]
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Create a new attachment, guesses the MIME type from the name if it's
+    /// not given
+    ///
+    /// ## Errors
+    /// - If the name is empty or it's a path
+    /// - If the data is larger than [`MAX_ATTACHMENT_SIZE`]
+    pub fn new(name: String, mime: Option<String>, data: Vec<u8>) -> LprsResult<Self> {
+        let attachment = Self {
+            mime: mime.unwrap_or_else(|| guess_mime(&name).to_owned()),
+            name,
+            data,
+        };
+        attachment.validate()?;
+        Ok(attachment)
+    }
+
+    /// Check the attachment name and size
+    ///
+    /// ## Errors
+    /// - If the name is empty or it's a path
+    /// - If the data is larger than [`MAX_ATTACHMENT_SIZE`]
+    pub fn validate(&self) -> LprsResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(LprsError::EmptyValue);
+        }
+        if self.name.contains(['/', '\\']) {
+            return Err(LprsError::Other(format!(
+                "The attachment name `{}` can't contain a path separator",
+                self.name
+            )));
+        }
+        if self.data.len() > MAX_ATTACHMENT_SIZE {
+            return Err(LprsError::Other(format!(
+                "The attachment `{}` is larger than the maximum size, {}",
+                self.name,
+                HumanSize(MAX_ATTACHMENT_SIZE)
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A size in bytes, displayed in a human readable unit
+pub struct HumanSize(pub usize);
+
+impl fmt::Display for HumanSize {
+    #[allow(clippy::cast_precision_loss)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut size = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Returns the MIME type of the file name from its extension,
+/// [`DEFAULT_MIME`] if it's unknown
+pub fn guess_mime(name: &str) -> &'static str {
+    Path::new(name)
+        .extension()
+        .and_then(|extension| {
+            let extension = extension.to_string_lossy();
+            MIME_TYPES
+                .iter()
+                .find(|(known, _)| extension.eq_ignore_ascii_case(known))
+        })
+        .map_or(DEFAULT_MIME, |(_, mime)| mime)
+}
+
+/// Check the vault attachments, their names must be unique and their sizes
+/// within the limits
+///
+/// ## Errors
+/// - If an attachment is invalid, see [`Attachment::validate`]
+/// - If two attachments have the same name
+/// - If the attachments are larger than [`MAX_VAULT_ATTACHMENTS_SIZE`]
+pub fn check_vault(vault: &Vault) -> LprsResult<()> {
+    let mut names = HashSet::new();
+    for attachment in &vault.attachments {
+        attachment.validate()?;
+        if !names.insert(attachment.name.as_str()) {
+            return Err(LprsError::Other(format!(
+                "The `{}` vault has two attachments named `{}`",
+                vault.name, attachment.name
+            )));
+        }
+    }
+    let total_size = vault.attachments.iter().map(|a| a.data.len()).sum();
+    if total_size > MAX_VAULT_ATTACHMENTS_SIZE {
+        return Err(LprsError::Other(format!(
+            "The `{}` vault attachments are {}, the maximum size is {}",
+            vault.name,
+            HumanSize(total_size),
+            HumanSize(MAX_VAULT_ATTACHMENTS_SIZE)
+        )));
+    }
+    Ok(())
+}
+
+/// Serialize the attachment data as base64
+fn serialize_data<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&crate::BASE64.encode(data))
+}
+
+/// Deserialize the base64 attachment data
+fn deserialize_data<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    crate::BASE64
+        .decode(String::deserialize(deserializer)?)
+        .map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,345 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The vaults audit, finds the weak, breached, reused, near duplicated and old
+//! passwords, and the vaults without TOTP where their service supports it
+
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{Vault, breach::BreachDb, strength::Strength};
+use crate::{LprsResult, config::policies, utils};
+
+/// The domains of the well known services that support TOTP, more can be
+/// given to the audit
+pub const TOTP_SERVICES: &[&str] = &[
+    "amazon.com",
+    "apple.com",
+    "atlassian.com",
+    "binance.com",
+    "bitbucket.org",
+    "cloudflare.com",
+    "codeberg.org",
+    "coinbase.com",
+    "digitalocean.com",
+    "discord.com",
+    "docker.com",
+    "dropbox.com",
+    "epicgames.com",
+    "facebook.com",
+    "fastmail.com",
+    "github.com",
+    "gitlab.com",
+    "godaddy.com",
+    "google.com",
+    "heroku.com",
+    "hetzner.com",
+    "instagram.com",
+    "kraken.com",
+    "linkedin.com",
+    "linode.com",
+    "live.com",
+    "mailchimp.com",
+    "microsoft.com",
+    "namecheap.com",
+    "npmjs.com",
+    "ovh.com",
+    "paypal.com",
+    "proton.me",
+    "pypi.org",
+    "reddit.com",
+    "slack.com",
+    "stripe.com",
+    "twitch.tv",
+    "twitter.com",
+    "vultr.com",
+    "wordpress.com",
+    "x.com",
+    "yahoo.com",
+    "zoom.us",
+];
+
+/// The maximum edit distance between two near duplicated passwords, the case
+/// is ignored
+pub const NEAR_DUPLICATE_DISTANCE: usize = 2;
+/// The minimum length of the passwords that are compared as near duplicates
+const NEAR_DUPLICATE_MIN_LENGTH: usize = 6;
+/// The seconds of a day
+const DAY_SECONDS: u64 = 60 * 60 * 24;
+
+/// The audit options
+#[derive(Debug)]
+pub struct AuditOptions {
+    /// The minimum password score, from 0 to 100
+    pub min_score:     f64,
+    /// The maximum password age in days
+    pub max_age:       u64,
+    /// The domains of the services that support TOTP
+    pub totp_services: Vec<String>,
+    /// The breached passwords file, to check the passwords against it
+    pub breach_db:     Option<BreachDb>,
+}
+
+/// A vault audit finding
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Finding {
+    /// The password score is lower than the minimum score
+    Weak {
+        /// The password score
+        score: f64,
+    },
+    /// The password is a common password
+    Common,
+    /// The password is in the breached passwords file
+    Breached {
+        /// How many times the password was seen in the breaches, `0` if the
+        /// file doesn't have the counts
+        count: u64,
+    },
+    /// The same password is used in other vaults
+    Reused {
+        /// The other vaults names
+        vaults: Vec<String>,
+    },
+    /// A similar password is used in other vaults
+    NearDuplicate {
+        /// The other vaults names
+        vaults: Vec<String>,
+    },
+    /// The password is older than the maximum age
+    Old {
+        /// The password age in days
+        days: u64,
+    },
+    /// The service supports TOTP but the vault doesn't have it
+    MissingTotp {
+        /// The service domain that supports TOTP
+        domain: String,
+    },
+}
+
+/// The audit of a vault that have a password
+#[derive(Debug, Serialize)]
+pub struct VaultAudit {
+    /// The vault index, starts from 1
+    pub index:    usize,
+    /// The vault name
+    pub name:     String,
+    /// The password score, from 0 to 100
+    pub score:    f64,
+    /// The password age in days, `None` if it's unknown
+    pub age:      Option<u64>,
+    /// The vault findings
+    pub findings: Vec<Finding>,
+}
+
+/// The vaults audit report
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    /// The audited vaults, the vaults that have a password
+    pub vaults: Vec<VaultAudit>,
+}
+
+impl AuditReport {
+    /// Returns the number of the findings
+    pub fn findings_count(&self) -> usize {
+        self.vaults.iter().map(|v| v.findings.len()).sum()
+    }
+
+    /// Returns the vaults that have findings
+    pub fn vaults_with_findings(&self) -> impl Iterator<Item = &VaultAudit> {
+        self.vaults.iter().filter(|v| !v.findings.is_empty())
+    }
+}
+
+/// Returns the edit (Levenshtein) distance between the two strings, stops
+/// early and returns a bigger distance than `max` if it's bigger than it
+fn edit_distance(first: &[char], second: &[char], max: usize) -> usize {
+    if first.len().abs_diff(second.len()) > max {
+        return max + 1;
+    }
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+    for (i, first_char) in first.iter().enumerate() {
+        let mut current = vec![i + 1; second.len() + 1];
+        for (j, second_char) in second.iter().enumerate() {
+            let substitution = previous[j] + usize::from(first_char != second_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|distance| *distance > max) {
+            return max + 1;
+        }
+        previous = current;
+    }
+    previous[second.len()]
+}
+
+/// Returns true if the two different passwords are near duplicates
+fn is_near_duplicate(first: &str, second: &str) -> bool {
+    let first: Vec<char> = first.to_lowercase().chars().collect();
+    let second: Vec<char> = second.to_lowercase().chars().collect();
+    first.len().min(second.len()) >= NEAR_DUPLICATE_MIN_LENGTH
+        && edit_distance(&first, &second, NEAR_DUPLICATE_DISTANCE) <= NEAR_DUPLICATE_DISTANCE
+}
+
+/// Audit the vaults that have a password
+///
+/// ## Errors
+/// - If the breached passwords file can't be read
+pub fn audit(vaults: &[Vault], options: &AuditOptions) -> LprsResult<AuditReport> {
+    let now = utils::unix_timestamp();
+    let passwords: Vec<(usize, &Vault, &str)> = vaults
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, vault)| vault.password.as_deref().map(|p| (idx + 1, vault, p)))
+        .collect();
+
+    let vaults = passwords
+        .iter()
+        .map(|(index, vault, password)| {
+            let mut findings = Vec::new();
+            let Strength {
+                score, is_common, ..
+            } = Strength::of(password);
+            if is_common {
+                findings.push(Finding::Common);
+            } else if score < options.min_score {
+                findings.push(Finding::Weak { score });
+            }
+            if let Some(ref breach_db) = options.breach_db {
+                if let Some(count) = breach_db.lookup(password)? {
+                    findings.push(Finding::Breached { count });
+                }
+            }
+
+            let others = passwords.iter().filter(|(idx, ..)| idx != index);
+            let reused: Vec<String> = others
+                .clone()
+                .filter(|(.., other)| other == password)
+                .map(|(_, other, _)| other.name.clone())
+                .collect();
+            if !reused.is_empty() {
+                findings.push(Finding::Reused { vaults: reused });
+            }
+            let similar: Vec<String> = others
+                .filter(|(.., other)| other != password && is_near_duplicate(password, other))
+                .map(|(_, other, _)| other.name.clone())
+                .collect();
+            if !similar.is_empty() {
+                findings.push(Finding::NearDuplicate { vaults: similar });
+            }
+
+            let age = vault
+                .password_changed
+                .map(|changed| now.saturating_sub(changed) / DAY_SECONDS);
+            if let Some(days) = age.filter(|days| *days > options.max_age) {
+                findings.push(Finding::Old { days });
+            }
+
+            if vault.totp_secret.is_none() {
+                let totp_domain = vault
+                    .service
+                    .as_deref()
+                    .and_then(policies::service_host)
+                    .and_then(|host| {
+                        options
+                            .totp_services
+                            .iter()
+                            .find(|domain| policies::is_domain_host(&host, domain))
+                    });
+                if let Some(domain) = totp_domain {
+                    findings.push(Finding::MissingTotp {
+                        domain: domain.clone(),
+                    });
+                }
+            }
+
+            Ok(VaultAudit {
+                index: *index,
+                name: vault.name.clone(),
+                score,
+                age,
+                findings,
+            })
+        })
+        .collect::<LprsResult<_>>()?;
+
+    Ok(AuditReport { vaults })
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Weak { score } => write!(f, "weak password (score {score:.0})"),
+            Self::Common => write!(f, "common password"),
+            Self::Breached { count: 0 } => write!(f, "breached password"),
+            Self::Breached { count } => write!(f, "breached password (seen {count} times)"),
+            Self::Reused { vaults } => write!(f, "reused in: {}", vaults.join(", ")),
+            Self::NearDuplicate { vaults } => write!(f, "similar to: {}", vaults.join(", ")),
+            Self::Old { days } => write!(f, "{days} days old"),
+            Self::MissingTotp { domain } => write!(f, "no TOTP, {domain} supports it"),
+        }
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<[String; 4]> = self
+            .vaults_with_findings()
+            .map(|vault| {
+                [
+                    vault.index.to_string(),
+                    vault.name.clone(),
+                    format!("{:.0}", vault.score),
+                    vault
+                        .age
+                        .map_or_else(|| "unknown".to_owned(), |days| format!("{days}d")),
+                ]
+            })
+            .collect();
+        let header = ["#", "Name", "Score", "Age"].map(ToOwned::to_owned);
+        let widths: Vec<usize> = (0..header.len())
+            .map(|col| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let columns = |row: &[String; 4]| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+
+        let mut lines = vec![format!("{}  Findings", columns(&header))];
+        for (row, vault) in rows.iter().zip(self.vaults_with_findings()) {
+            for (idx, finding) in vault.findings.iter().enumerate() {
+                let cells = if idx == 0 {
+                    columns(row)
+                } else {
+                    columns(&Default::default())
+                };
+                lines.push(format!("{cells}  {finding}"));
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,241 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The encrypted backup is a single authenticated encrypted blob of the
+//! plaintext JSON export, with its own KDF header:
+//!
+//! | Bytes | Content                                         |
+//! | ----- | ----------------------------------------------- |
+//! | 11    | The magic `LPRS\0BACKUP`                        |
+//! | 1     | The backup version, currently `1`               |
+//! | 4     | The Argon2id memory cost in KiB, little-endian  |
+//! | 4     | The Argon2id iterations, little-endian          |
+//! | 4     | The Argon2id parallelism, little-endian         |
+//! | 16    | The Argon2id salt                               |
+//! | 12    | The AES-256-GCM nonce                           |
+//! | ..    | The AES-256-GCM ciphertext and its tag          |
+//!
+//! The key is Argon2id of the SHA-256 of the password, and the whole header is
+//! authenticated as the associated data.
+
+use aes_gcm::{
+    Aes256Gcm,
+    Key,
+    Nonce,
+    aead::{Aead, KeyInit, Payload},
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use super::{Vault, plaintext};
+use crate::{LprsError, LprsResult};
+
+/// The magic bytes of the encrypted backup
+const BACKUP_MAGIC: &[u8] = b"LPRS\0BACKUP";
+/// The current version of the encrypted backup
+const BACKUP_VERSION: u8 = 1;
+/// The Argon2id salt length
+const SALT_LEN: usize = 16;
+/// The AES-256-GCM nonce length
+const NONCE_LEN: usize = 12;
+/// The header length, the magic, the version, the KDF parameters, the salt
+/// and the nonce
+const HEADER_LEN: usize = BACKUP_MAGIC.len() + 1 + 4 * 3 + SALT_LEN + NONCE_LEN;
+
+/// The Argon2id memory cost of the new backups, in KiB (64 MiB)
+const MEMORY_COST: u32 = 64 * 1024;
+/// The Argon2id iterations of the new backups
+const TIME_COST: u32 = 3;
+/// The Argon2id parallelism of the new backups
+const PARALLELISM: u32 = 1;
+/// The maximum Argon2id memory cost accepted from a backup header, in KiB (1
+/// GiB). Protects from the backups that ask for more memory than we have
+const MAX_MEMORY_COST: u32 = 1024 * 1024;
+/// The maximum Argon2id iterations accepted from a backup header
+const MAX_TIME_COST: u32 = 64;
+
+/// Returns true if the data is an encrypted backup, checks only its magic
+pub fn is_backup(data: &[u8]) -> bool {
+    data.starts_with(BACKUP_MAGIC)
+}
+
+/// Derive the AES-256 key from the password hash with Argon2id
+///
+/// ## Errors
+/// - If the parameters are invalid
+fn derive_key(
+    password_hash: &[u8; 32],
+    salt: &[u8],
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+) -> LprsResult<[u8; 32]> {
+    let params = Params::new(memory_cost, time_cost, parallelism, Some(32))
+        .map_err(|err| LprsError::Other(format!("Invalid backup KDF parameters: {err}")))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password_hash, salt, &mut key)
+        .map_err(|err| LprsError::Encryption(err.to_string()))?;
+    Ok(key)
+}
+
+/// Read a little-endian `u32` from the data at the given offset
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(
+        data[offset..offset + 4]
+            .try_into()
+            .expect("The slice is 4 bytes"),
+    )
+}
+
+/// Export the vaults as encrypted backup, encrypted with the given password
+/// hash (the SHA-256 of the password).
+///
+/// ## Errors
+/// - If the serialization or the encryption failed
+pub fn export(vaults: &[Vault], password_hash: &[u8; 32]) -> LprsResult<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::RngCore::fill_bytes(&mut rand::rng(), &mut salt);
+    rand::RngCore::fill_bytes(&mut rand::rng(), &mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(BACKUP_MAGIC);
+    header.push(BACKUP_VERSION);
+    header.extend_from_slice(&MEMORY_COST.to_le_bytes());
+    header.extend_from_slice(&TIME_COST.to_le_bytes());
+    header.extend_from_slice(&PARALLELISM.to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let key = derive_key(password_hash, &salt, MEMORY_COST, TIME_COST, PARALLELISM)?;
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext::export(vaults)?.as_bytes(),
+                aad: &header,
+            },
+        )
+        .map_err(|err| LprsError::Encryption(err.to_string()))?;
+
+    header.extend(ciphertext);
+    Ok(header)
+}
+
+/// Decrypt the encrypted backup with the given password hash (the SHA-256 of
+/// the password) and returns its vaults
+///
+/// ## Errors
+/// - If the data is not an encrypted backup, or its version is not supported
+/// - If the KDF parameters are invalid or too expensive
+/// - If the decryption failed (wrong password or the backup is tampered with)
+/// - If the decrypted data is not a valid plaintext export
+pub fn import(backup_data: &[u8], password_hash: &[u8; 32]) -> LprsResult<Vec<Vault>> {
+    if !is_backup(backup_data) || backup_data.len() < HEADER_LEN {
+        return Err(LprsError::Other(
+            "The data is not a valid lprs encrypted backup".to_owned(),
+        ));
+    }
+    let (header, ciphertext) = backup_data.split_at(HEADER_LEN);
+    let version = header[BACKUP_MAGIC.len()];
+    if version != BACKUP_VERSION {
+        return Err(LprsError::Other(format!(
+            "The encrypted backup version {version} is not supported, update lprs to import it"
+        )));
+    }
+
+    let params_offset = BACKUP_MAGIC.len() + 1;
+    let memory_cost = read_u32(header, params_offset);
+    let time_cost = read_u32(header, params_offset + 4);
+    let parallelism = read_u32(header, params_offset + 8);
+    if memory_cost > MAX_MEMORY_COST || time_cost > MAX_TIME_COST {
+        return Err(LprsError::Other(format!(
+            "The encrypted backup KDF parameters are too expensive ({memory_cost} KiB memory, \
+             {time_cost} iterations)"
+        )));
+    }
+    let salt = &header[params_offset + 12..][..SALT_LEN];
+    let nonce = &header[params_offset + 12 + SALT_LEN..][..NONCE_LEN];
+
+    let key = derive_key(password_hash, salt, memory_cost, time_cost, parallelism)?;
+    let plaintext_data = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| LprsError::Decryption)?;
+
+    plaintext::import(&plaintext_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::vault::cipher::TotpHash;
+
+    /// Returns the test vaults
+    fn vaults() -> Vec<Vault> {
+        vec![Vault::new(
+            "GitHub",
+            Some("alice"),
+            Some("Xk9#mQ2$vL7!pR4z"),
+            Some("https://github.com"),
+            Some("line 1\nline 2"),
+            BTreeMap::from([("pin".to_owned(), "1234".to_owned())]),
+            Some("JBSWY3DPEHPK3PXP"),
+            TotpHash::Sha256,
+            8,
+        )]
+    }
+
+    #[test]
+    fn round_trip() {
+        let backup = export(&vaults(), &[1; 32]).unwrap();
+        assert!(is_backup(&backup));
+        assert_eq!(import(&backup, &[1; 32]).unwrap(), vaults());
+    }
+
+    #[test]
+    fn wrong_password() {
+        let backup = export(&vaults(), &[1; 32]).unwrap();
+        assert!(matches!(
+            import(&backup, &[2; 32]),
+            Err(LprsError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn tampered_header() {
+        let mut backup = export(&vaults(), &[1; 32]).unwrap();
+        // The salt is authenticated as the associated data
+        backup[HEADER_LEN - NONCE_LEN - 1] ^= 1;
+        assert!(matches!(
+            import(&backup, &[1; 32]),
+            Err(LprsError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn not_a_backup() {
+        assert!(!is_backup(b"{\"format\": \"lprs-plaintext\"}"));
+        assert!(import(BACKUP_MAGIC, &[1; 32]).is_err());
+    }
+}
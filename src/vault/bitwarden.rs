@@ -0,0 +1,574 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    Vault,
+    cipher::TotpHash,
+    custom_field::FieldKind,
+    item::{self, Card, Identity, Item, SshKey},
+    otpauth,
+};
+use crate::{LprsError, LprsResult};
+
+/// The Bitwarden login item type
+const LOGIN_TYPE: u8 = 1;
+/// The Bitwarden secure note item type
+const SECURE_NOTE_TYPE: u8 = 2;
+/// The Bitwarden card item type
+const CARD_TYPE: u8 = 3;
+/// The Bitwarden identity item type
+const IDENTITY_TYPE: u8 = 4;
+/// The Bitwarden SSH key item type
+const SSH_KEY_TYPE: u8 = 5;
+/// The Bitwarden card fields that are mapped to the card item
+const CARD_KEYS: &[&str] = &[
+    "cardholderName",
+    "brand",
+    "number",
+    "expMonth",
+    "expYear",
+    "code",
+];
+/// The Bitwarden identity name fields, joined as the identity full name
+const IDENTITY_NAME_KEYS: &[&str] = &["firstName", "middleName", "lastName"];
+/// The Bitwarden identity address fields, joined as the identity address
+const IDENTITY_ADDRESS_KEYS: &[&str] = &[
+    "address1",
+    "address2",
+    "address3",
+    "city",
+    "state",
+    "postalCode",
+    "country",
+];
+/// The Bitwarden SSH key fields that are mapped to the SSH key item
+const SSH_KEY_KEYS: &[&str] = &["privateKey", "publicKey", "keyFingerprint"];
+/// The Bitwarden text custom field type
+const TEXT_FIELD_TYPE: u8 = 0;
+/// The Bitwarden hidden custom field type
+const HIDDEN_FIELD_TYPE: u8 = 1;
+/// The Bitwarden boolean custom field type
+const BOOLEAN_FIELD_TYPE: u8 = 2;
+
+/// The Bitwarden unencrypted JSON export
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitWardenVaults {
+    /// Whether the export is encrypted, only the unencrypted one is supported
+    #[serde(default)]
+    pub encrypted: bool,
+    /// The export folders
+    #[serde(default)]
+    pub folders:   Vec<BitWardenFolder>,
+    /// The export items
+    #[serde(default)]
+    pub items:     Vec<BitWardenItem>,
+}
+
+/// The Bitwarden folder
+#[derive(Deserialize, Serialize)]
+pub struct BitWardenFolder {
+    /// The folder id, used in the items `folderId`
+    pub id:   String,
+    /// The folder name
+    pub name: String,
+}
+
+/// The Bitwarden item, the login item is the only one that have the `login`
+/// object
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitWardenItem {
+    /// The item id
+    #[serde(default)]
+    pub id:          Option<String>,
+    /// The item folder id
+    #[serde(default)]
+    pub folder_id:   Option<String>,
+    /// The item type. 1: login, 2: secure note, 3: card, 4: identity, 5: SSH
+    /// key
+    #[serde(rename = "type")]
+    pub item_type:   u8,
+    /// The item name
+    pub name:        String,
+    /// The item notes
+    #[serde(default)]
+    pub notes:       Option<String>,
+    /// The item custom fields
+    #[serde(default)]
+    pub fields:      Vec<BitWardenField>,
+    /// The login information, for the login items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login:       Option<BitWardenLogin>,
+    /// The secure note information, for the secure note items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secure_note: Option<Value>,
+    /// The card information, for the card items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card:        Option<Value>,
+    /// The identity information, for the identity items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity:    Option<Value>,
+    /// The SSH key information, for the SSH key items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key:     Option<Value>,
+}
+
+/// The Bitwarden custom field
+#[derive(Deserialize, Serialize)]
+pub struct BitWardenField {
+    /// The field name
+    #[serde(default)]
+    pub name:       Option<String>,
+    /// The field value
+    #[serde(default)]
+    pub value:      Option<String>,
+    /// The field type. 0: text, 1: hidden, 2: boolean, 3: linked
+    #[serde(rename = "type")]
+    pub field_type: u8,
+}
+
+/// The Bitwarden login information
+#[derive(Default, Deserialize, Serialize)]
+pub struct BitWardenLogin {
+    /// The login URIs
+    #[serde(default)]
+    pub uris:     Vec<BitWardenUri>,
+    /// The login username
+    #[serde(default)]
+    pub username: Option<String>,
+    /// The login password
+    #[serde(default)]
+    pub password: Option<String>,
+    /// The login TOTP, base32 secret or `otpauth://` URI
+    #[serde(default)]
+    pub totp:     Option<String>,
+}
+
+/// The Bitwarden login URI
+#[derive(Deserialize, Serialize)]
+pub struct BitWardenUri {
+    /// The URI match detection, `null` is the default one
+    #[serde(rename = "match")]
+    pub mt:  Option<u8>,
+    /// The URI
+    pub uri: Option<String>,
+}
+
+/// Returns a random UUID v4, used as Bitwarden ids
+fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    rand::RngCore::fill_bytes(&mut rand::rng(), &mut bytes);
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    let hex = bytes.iter().fold(String::with_capacity(32), |mut hex, b| {
+        write!(&mut hex, "{b:02x}").expect("String never fail");
+        hex
+    });
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Flatten the string values of the given Bitwarden object to the custom
+/// fields, used for the fields that don't have an equivalent in the vault.
+/// The `skipped` keys are not flattened
+fn flatten_object(object: &Value, fields: &mut BTreeMap<String, String>, skipped: &[&str]) {
+    if let Value::Object(map) = object {
+        for (key, value) in map
+            .iter()
+            .filter(|(key, _)| !skipped.contains(&key.as_str()))
+        {
+            match value {
+                Value::String(value) if !value.is_empty() => {
+                    fields.insert(key.clone(), value.clone());
+                }
+                Value::Number(value) => {
+                    fields.insert(key.clone(), value.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns the Bitwarden custom field type of the secret and the non-secret
+/// fields
+const fn field_type(is_secret: bool) -> u8 {
+    if is_secret {
+        HIDDEN_FIELD_TYPE
+    } else {
+        TEXT_FIELD_TYPE
+    }
+}
+
+/// Returns the kind of the Bitwarden text field, Bitwarden don't have the
+/// multi-line fields kind
+fn text_kind(value: &str) -> FieldKind {
+    if value.contains('\n') {
+        FieldKind::Multiline
+    } else {
+        FieldKind::Text
+    }
+}
+
+/// Returns the non empty string field of the Bitwarden object
+fn str_field(object: &Value, key: &str) -> Option<String> {
+    object
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|value| !value.trim().is_empty())
+        .map(ToOwned::to_owned)
+}
+
+/// Returns the non empty string fields of the Bitwarden object joined with
+/// the separator
+fn join_fields(object: &Value, keys: &[&str], separator: &str) -> Option<String> {
+    let values: Vec<String> = keys
+        .iter()
+        .filter_map(|key| str_field(object, key))
+        .collect();
+    (!values.is_empty()).then(|| values.join(separator))
+}
+
+impl BitWardenItem {
+    /// Returns the vault item of the Bitwarden item, `None` if it's a login or
+    /// its fields are invalid (they will be custom fields)
+    fn item(&self) -> Option<Item> {
+        let item = match self.item_type {
+            SECURE_NOTE_TYPE => Item::Note,
+            CARD_TYPE => {
+                let card = self.card.as_ref()?;
+                Item::Card(Card {
+                    number: str_field(card, "number")?,
+                    holder: str_field(card, "cardholderName"),
+                    expiry: str_field(card, "expMonth")
+                        .zip(str_field(card, "expYear"))
+                        .map(|(month, year)| format!("{month}/{year}")),
+                    cvv:    str_field(card, "code"),
+                })
+            }
+            IDENTITY_TYPE => {
+                let identity = self.identity.as_ref()?;
+                Item::Identity(Identity {
+                    full_name: join_fields(identity, IDENTITY_NAME_KEYS, " "),
+                    email:     str_field(identity, "email"),
+                    phone:     str_field(identity, "phone"),
+                    address:   join_fields(identity, IDENTITY_ADDRESS_KEYS, ", "),
+                })
+            }
+            SSH_KEY_TYPE => {
+                let ssh_key = self.ssh_key.as_ref()?;
+                Item::SshKey(SshKey {
+                    private_key: str_field(ssh_key, "privateKey")?,
+                    public_key:  str_field(ssh_key, "publicKey"),
+                })
+            }
+            _ => return None,
+        };
+        item.normalized()
+            .map_err(|err| {
+                log::warn!(
+                    "The `{}` item fields are imported as custom fields: {err}",
+                    self.name
+                );
+            })
+            .ok()
+    }
+
+    /// Convert the Bitwarden item to a vault
+    ///
+    /// ## Errors
+    /// - If the login TOTP is unsupported `otpauth://` URI
+    fn into_vault(self, folders: &HashMap<String, String>) -> LprsResult<Vault> {
+        let item = self.item();
+        let mut custom_fields = BTreeMap::new();
+        let mut kinds = Vec::new();
+        for field in self.fields {
+            if let (Some(name), Some(value)) = (field.name, field.value) {
+                if !name.is_empty() && !value.is_empty() {
+                    if matches!(field.field_type, TEXT_FIELD_TYPE | BOOLEAN_FIELD_TYPE) {
+                        kinds.push((name.clone(), text_kind(&value)));
+                    }
+                    custom_fields.insert(name, value);
+                }
+            }
+        }
+        let identity_keys = [
+            IDENTITY_NAME_KEYS,
+            IDENTITY_ADDRESS_KEYS,
+            &["email", "phone"],
+        ]
+        .concat();
+        for (object, mapped_keys) in [
+            (&self.card, CARD_KEYS),
+            (&self.identity, identity_keys.as_slice()),
+            (&self.ssh_key, SSH_KEY_KEYS),
+        ] {
+            if let Some(object) = object {
+                // The mapped fields of the typed items are not custom fields
+                let skipped = if item.is_some() { mapped_keys } else { &[] };
+                flatten_object(object, &mut custom_fields, skipped);
+            }
+        }
+
+        let login = self.login.unwrap_or_default();
+        let mut uris = login.uris.into_iter().filter_map(|u| u.uri);
+        let service = uris.next();
+        for (idx, uri) in uris.enumerate() {
+            let key = format!("URI {}", idx + 2);
+            let kind = if FieldKind::Url.check(&key, &uri).is_ok() {
+                FieldKind::Url
+            } else {
+                text_kind(&uri)
+            };
+            kinds.push((key.clone(), kind));
+            custom_fields.insert(key, uri);
+        }
+
+        let (totp_secret, totp_hash, totp_digits) = match login
+            .totp
+            .as_deref()
+            .map(otpauth::parse_totp_field)
+            .transpose()?
+            .flatten()
+        {
+            Some((secret, hash, digits)) => (Some(secret), hash, digits),
+            None => (None, TotpHash::default(), otpauth::DEFAULT_DIGITS),
+        };
+
+        let mut vault = Vault {
+            folder: self
+                .folder_id
+                .and_then(|folder_id| folders.get(&folder_id).cloned()),
+            item: item.unwrap_or_default(),
+            ..Vault::new(
+                self.name,
+                login.username.filter(|u| !u.is_empty()),
+                login.password.filter(|p| !p.is_empty()),
+                service,
+                self.notes.filter(|n| !n.is_empty()),
+                custom_fields,
+                totp_secret,
+                totp_hash,
+                totp_digits,
+            )
+        };
+        for (key, kind) in kinds {
+            vault.set_field_kind(&key, kind);
+        }
+        Ok(vault)
+    }
+
+    /// Create Bitwarden item from the vault
+    fn from_vault(vault: &Vault, folders: &HashMap<String, String>) -> Self {
+        // Bitwarden don't have the API tokens, they are logins with custom fields
+        if let Item::ApiToken(_) = vault.item {
+            return Self::from_vault(&vault.flatten_item(), folders);
+        }
+        let mut fields: Vec<BitWardenField> = vault
+            .custom_fields
+            .iter()
+            .map(|(key, value)| {
+                BitWardenField {
+                    name:       Some(key.clone()),
+                    value:      Some(value.clone()),
+                    field_type: field_type(vault.field_kind(key).is_secret()),
+                }
+            })
+            .collect();
+        let (mut card, mut identity, mut ssh_key) = (None, None, None);
+        let item_type = match vault.item {
+            Item::Login | Item::ApiToken(_) => {
+                let is_note = vault.username.is_none()
+                    && vault.password.is_none()
+                    && vault.service.is_none()
+                    && vault.totp_secret.is_none();
+                if is_note {
+                    SECURE_NOTE_TYPE
+                } else {
+                    LOGIN_TYPE
+                }
+            }
+            Item::Note => SECURE_NOTE_TYPE,
+            Item::Card(ref vault_card) => {
+                let (month, year) = vault_card
+                    .expiry
+                    .as_deref()
+                    .and_then(|expiry| expiry.split_once('/'))
+                    .unzip();
+                card = Some(serde_json::json!({
+                    "cardholderName": vault_card.holder,
+                    "brand": item::card_brand(&vault_card.number),
+                    "number": vault_card.number,
+                    "expMonth": month.map(|m| m.trim_start_matches('0')),
+                    "expYear": year,
+                    "code": vault_card.cvv,
+                }));
+                CARD_TYPE
+            }
+            Item::Identity(ref vault_identity) => {
+                // The full name is split to the first name and the last name
+                let (first_name, last_name) = vault_identity
+                    .full_name
+                    .as_deref()
+                    .map(|name| name.split_once(' ').unwrap_or((name, "")))
+                    .unzip();
+                identity = Some(serde_json::json!({
+                    "firstName": first_name,
+                    "lastName": last_name.filter(|name| !name.is_empty()),
+                    "email": vault_identity.email,
+                    "phone": vault_identity.phone,
+                    "address1": vault_identity.address,
+                }));
+                IDENTITY_TYPE
+            }
+            Item::SshKey(ref vault_ssh_key) => {
+                ssh_key = Some(serde_json::json!({
+                    "privateKey": vault_ssh_key.private_key,
+                    "publicKey": vault_ssh_key.public_key,
+                    "keyFingerprint": vault_ssh_key.fingerprint(),
+                }));
+                SSH_KEY_TYPE
+            }
+        };
+        // Only the login items have the login fields, they are custom fields in
+        // the other items
+        if item_type != LOGIN_TYPE {
+            for (name, value, is_secret) in [
+                ("Username", &vault.username, false),
+                ("Password", &vault.password, true),
+                ("Service", &vault.service, false),
+                ("TOTP", &vault.totp_secret, true),
+            ] {
+                if let Some(value) = value {
+                    fields.push(BitWardenField {
+                        name:       Some(name.to_owned()),
+                        value:      Some(value.clone()),
+                        field_type: field_type(is_secret),
+                    });
+                }
+            }
+        }
+
+        // The secure note type 0 is the generic note
+        let secure_note = (item_type == SECURE_NOTE_TYPE).then(|| serde_json::json!({ "type": 0 }));
+
+        Self {
+            id: Some(random_uuid()),
+            folder_id: vault
+                .folder
+                .as_ref()
+                .and_then(|folder| folders.get(folder).cloned()),
+            item_type,
+            name: vault.name.clone(),
+            notes: vault.note.clone(),
+            fields,
+            login: (item_type == LOGIN_TYPE).then(|| {
+                BitWardenLogin {
+                    uris:     vault
+                        .service
+                        .iter()
+                        .map(|service| {
+                            BitWardenUri {
+                                mt:  None,
+                                uri: Some(service.clone()),
+                            }
+                        })
+                        .collect(),
+                    username: vault.username.clone(),
+                    password: vault.password.clone(),
+                    totp:     otpauth::totp_field(vault),
+                }
+            }),
+            secure_note,
+            card,
+            identity,
+            ssh_key,
+        }
+    }
+}
+
+impl BitWardenVaults {
+    /// Parse the Bitwarden unencrypted JSON export and returns its vaults
+    ///
+    /// ## Errors
+    /// - If the data is not valid Bitwarden JSON
+    /// - If the export is encrypted
+    /// - If there is an unsupported `otpauth://` URI
+    pub fn import(json_data: &[u8]) -> LprsResult<Vec<Vault>> {
+        let bitwarden: Self = serde_json::from_slice(json_data)?;
+        if bitwarden.encrypted {
+            return Err(LprsError::Other(
+                "The encrypted Bitwarden exports are not supported, export it as unencrypted JSON"
+                    .to_owned(),
+            ));
+        }
+
+        let folders: HashMap<String, String> = bitwarden
+            .folders
+            .into_iter()
+            .map(|folder| (folder.id, folder.name))
+            .collect();
+        bitwarden
+            .items
+            .into_iter()
+            .map(|item| item.into_vault(&folders))
+            .collect()
+    }
+
+    /// Export the vaults as Bitwarden unencrypted JSON
+    ///
+    /// ## Errors
+    /// - If the serialization failed
+    pub fn export(vaults: &[Vault]) -> LprsResult<String> {
+        let mut folders = Vec::new();
+        let mut folder_ids = HashMap::new();
+        for folder in vaults.iter().filter_map(|v| v.folder.as_ref()) {
+            if !folder_ids.contains_key(folder) {
+                let id = random_uuid();
+                folder_ids.insert(folder.clone(), id.clone());
+                folders.push(BitWardenFolder {
+                    id,
+                    name: folder.clone(),
+                });
+            }
+        }
+
+        serde_json::to_string_pretty(&Self {
+            encrypted: false,
+            folders,
+            items: vaults
+                .iter()
+                .map(|vault| BitWardenItem::from_vault(vault, &folder_ids))
+                .collect(),
+        })
+        .map_err(Into::into)
+    }
+}
@@ -0,0 +1,277 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The breached passwords lookup, in a local HaveIBeenPwned hashes file
+//! <https://haveibeenpwned.com/Passwords>
+//!
+//! The file must be ordered by hash, a `HASH:COUNT` line for each password,
+//! the hash is the uppercase hex of the SHA-1 or the NTLM hash of the
+//! password. The lookup is a binary search over the file bytes, so only a few
+//! lines are read for each password even in the huge files.
+
+use std::{
+    cmp::Ordering,
+    fmt::Write as _,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+
+use sha1::{Digest, Sha1};
+
+use crate::{LprsError, LprsResult};
+
+/// The hash function of the breached passwords file
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BreachHash {
+    /// SHA-1 of the password, 40 hex characters
+    Sha1,
+    /// NTLM (MD4 of the UTF-16LE password), 32 hex characters
+    Ntlm,
+}
+
+/// A local breached passwords file, ordered by hash
+#[derive(Debug)]
+pub struct BreachDb {
+    /// The opened file
+    file: File,
+    /// The file length in bytes
+    len:  u64,
+    /// The hash function of the file
+    hash: BreachHash,
+}
+
+impl BreachHash {
+    /// Returns the hash function of the hex hash by its length
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            40 => Some(Self::Sha1),
+            32 => Some(Self::Ntlm),
+            _ => None,
+        }
+    }
+
+    /// Returns the uppercase hex hash of the password
+    pub fn hash(&self, password: &str) -> String {
+        let digest = match self {
+            Self::Sha1 => Sha1::digest(password).to_vec(),
+            Self::Ntlm => {
+                md4(&password
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes)
+                    .collect::<Vec<u8>>())
+                .to_vec()
+            }
+        };
+        digest.iter().fold(String::with_capacity(40), |mut hex, b| {
+            write!(&mut hex, "{b:02X}").expect("String never fail");
+            hex
+        })
+    }
+}
+
+impl BreachDb {
+    /// Open the breached passwords file, the hash function is detected from
+    /// the first line
+    ///
+    /// ## Errors
+    /// - If the file can't be opened or read
+    /// - If the first line isn't a SHA-1 or NTLM hash line
+    pub fn open(path: &Path) -> LprsResult<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut first_line = String::new();
+        BufReader::new(&file).read_line(&mut first_line)?;
+        let first_hash = line_hash(first_line.as_bytes());
+        let hash = BreachHash::from_hex_len(first_hash.len())
+            .filter(|_| first_hash.iter().all(u8::is_ascii_hexdigit))
+            .ok_or_else(|| {
+                LprsError::Other(format!(
+                    "Invalid breached passwords file `{}`, the lines must be `HASH:COUNT` with a \
+                     SHA-1 or NTLM hash",
+                    path.display()
+                ))
+            })?;
+        log::debug!("The breached passwords file hash function is {hash:?}");
+
+        Ok(Self { file, len, hash })
+    }
+
+    /// Returns the hash function of the file
+    pub const fn hash(&self) -> BreachHash {
+        self.hash
+    }
+
+    /// Returns the first line that starts at or after the offset, with its
+    /// start offset. `None` if there is no line after it
+    fn line_from(&self, offset: u64) -> LprsResult<Option<(u64, Vec<u8>)>> {
+        let mut reader = BufReader::with_capacity(256, &self.file);
+        let mut start = offset;
+        let mut line = Vec::new();
+        if offset != 0 {
+            // The offset is a line start only if the previous byte is a newline
+            reader.seek(SeekFrom::Start(offset - 1))?;
+            start += reader.read_until(b'\n', &mut line)? as u64 - 1;
+            line.clear();
+        } else {
+            reader.seek(SeekFrom::Start(0))?;
+        }
+        if start >= self.len || reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start, line)))
+    }
+
+    /// Returns the breaches count of the password if it's in the file, the
+    /// count is `0` if the line has no count
+    ///
+    /// ## Errors
+    /// - If the file can't be read
+    pub fn lookup(&self, password: &str) -> LprsResult<Option<u64>> {
+        let target = self.hash.hash(password);
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let Some((start, line)) = self.line_from(middle)? else {
+                high = middle;
+                continue;
+            };
+            match line_hash(&line)
+                .to_ascii_uppercase()
+                .as_slice()
+                .cmp(target.as_bytes())
+            {
+                Ordering::Less => low = start + line.len() as u64,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => {
+                    let count = line
+                        .split(|b| *b == b':')
+                        .nth(1)
+                        .and_then(|count| std::str::from_utf8(count).ok())
+                        .and_then(|count| count.trim().parse().ok())
+                        .unwrap_or_default();
+                    return Ok(Some(count));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the hash of the `HASH:COUNT` line
+fn line_hash(line: &[u8]) -> &[u8] {
+    let hash = line.split(|b| *b == b':').next().unwrap_or_default();
+    let end = hash
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |idx| idx + 1);
+    &hash[..end]
+}
+
+/// The MD4 hash function (RFC 1320), used only for the NTLM hashes
+fn md4(data: &[u8]) -> [u8; 16] {
+    let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+    let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+    let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for block in message.chunks_exact(64) {
+        let x: Vec<u32> = block
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().expect("The word is 4 bytes")))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+
+        for i in [0, 4, 8, 12] {
+            a = a.wrapping_add(f(b, c, d)).wrapping_add(x[i]).rotate_left(3);
+            d = d
+                .wrapping_add(f(a, b, c))
+                .wrapping_add(x[i + 1])
+                .rotate_left(7);
+            c = c
+                .wrapping_add(f(d, a, b))
+                .wrapping_add(x[i + 2])
+                .rotate_left(11);
+            b = b
+                .wrapping_add(f(c, d, a))
+                .wrapping_add(x[i + 3])
+                .rotate_left(19);
+        }
+        for i in 0..4 {
+            let k = 0x5a82_7999;
+            a = a
+                .wrapping_add(g(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(k)
+                .rotate_left(3);
+            d = d
+                .wrapping_add(g(a, b, c))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(k)
+                .rotate_left(5);
+            c = c
+                .wrapping_add(g(d, a, b))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(k)
+                .rotate_left(9);
+            b = b
+                .wrapping_add(g(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(k)
+                .rotate_left(13);
+        }
+        for i in [0, 2, 1, 3] {
+            let k = 0x6ed9_eba1;
+            a = a
+                .wrapping_add(h(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(k)
+                .rotate_left(3);
+            d = d
+                .wrapping_add(h(a, b, c))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(k)
+                .rotate_left(9);
+            c = c
+                .wrapping_add(h(d, a, b))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(k)
+                .rotate_left(11);
+            b = b
+                .wrapping_add(h(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(k)
+                .rotate_left(15);
+        }
+
+        for (word, new) in state.iter_mut().zip([a, b, c, d]) {
+            *word = word.wrapping_add(new);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
@@ -43,16 +43,21 @@ pub enum TotpHash {
 ///
 /// ## Errors
 /// - If the given `secret_base32` are invalid base32
-pub fn totp_now(secret_base32: &str, hash_function: &TotpHash) -> LprsResult<(String, u8)> {
+pub fn totp_now(
+    secret_base32: &str,
+    hash_function: &TotpHash,
+    digits: u8,
+) -> LprsResult<(String, u8)> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("SystemTime before UNIX EPOCH!")
         .as_secs();
     let secret = base32_decode(secret_base32)?;
+    let digits = u32::from(digits);
     let totp_code = match hash_function {
-        TotpHash::Sha1 => totp_lite::totp_custom::<totp_lite::Sha1>(30, 6, &secret, now),
-        TotpHash::Sha256 => totp_lite::totp_custom::<totp_lite::Sha256>(30, 6, &secret, now),
-        TotpHash::Sha512 => totp_lite::totp_custom::<totp_lite::Sha512>(30, 6, &secret, now),
+        TotpHash::Sha1 => totp_lite::totp_custom::<totp_lite::Sha1>(30, digits, &secret, now),
+        TotpHash::Sha256 => totp_lite::totp_custom::<totp_lite::Sha256>(30, digits, &secret, now),
+        TotpHash::Sha512 => totp_lite::totp_custom::<totp_lite::Sha512>(30, digits, &secret, now),
     };
     Ok((totp_code, 30 - (now % 30) as u8))
 }
@@ -0,0 +1,503 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    convert::Infallible,
+    fmt,
+    str::FromStr,
+};
+
+use ::csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use clap::ValueEnum;
+
+use super::{Vault, cipher::TotpHash, otpauth};
+use crate::{LprsError, LprsResult};
+
+/// The vault fields, in the default columns order
+const VAULT_FIELDS: [CsvField; 7] = [
+    CsvField::Name,
+    CsvField::Username,
+    CsvField::Password,
+    CsvField::Service,
+    CsvField::Note,
+    CsvField::Folder,
+    CsvField::Totp,
+];
+
+/// The columns indexes with their assigned vault fields
+type AssignedColumns = Vec<(usize, CsvField)>;
+
+/// A vault field that can be a CSV column
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CsvField {
+    /// The vault name
+    Name,
+    /// The vault username
+    Username,
+    /// The vault password
+    Password,
+    /// The vault service
+    Service,
+    /// The vault note
+    Note,
+    /// The vault folder
+    Folder,
+    /// The vault TOTP, base32 secret or `otpauth://` URI
+    Totp,
+    /// A vault custom field
+    Custom(String),
+}
+
+/// A CSV column and the vault field of it, written as `field=column`, or just
+/// `field` if the column have the same name of the field
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CsvColumn {
+    /// The vault field
+    pub field:  CsvField,
+    /// The CSV column name
+    pub column: String,
+}
+
+/// The CSV layouts of the other managers exports
+#[derive(Debug, Clone, Copy, ValueEnum, Eq, PartialEq)]
+pub enum CsvPreset {
+    /// The Chrome (and Chromium based browsers) passwords CSV
+    Chrome,
+    /// The Firefox logins CSV
+    Firefox,
+    /// The 1Password CSV export
+    #[value(name = "1password")]
+    OnePassword,
+    /// The LastPass CSV export
+    #[value(name = "lastpass")]
+    LastPass,
+}
+
+impl CsvField {
+    /// Returns the field name, as used in the columns mapping
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Name => "name",
+            Self::Username => "username",
+            Self::Password => "password",
+            Self::Service => "service",
+            Self::Note => "note",
+            Self::Folder => "folder",
+            Self::Totp => "totp",
+            Self::Custom(key) => key,
+        }
+    }
+
+    /// Returns the field value of the given vault
+    fn value(&self, vault: &Vault) -> Option<String> {
+        match self {
+            Self::Name => Some(vault.name.clone()),
+            Self::Username => vault.username.clone(),
+            Self::Password => vault.password.clone(),
+            Self::Service => vault.service.clone(),
+            Self::Note => vault.note.clone(),
+            Self::Folder => vault.folder.clone(),
+            Self::Totp => otpauth::totp_field(vault),
+            Self::Custom(key) => vault.custom_fields.get(key).cloned(),
+        }
+    }
+}
+
+impl FromStr for CsvField {
+    type Err = Infallible;
+
+    /// Parse the field name, the unknown names are custom fields
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(VAULT_FIELDS
+            .into_iter()
+            .find(|field| field.as_str().eq_ignore_ascii_case(name))
+            .unwrap_or_else(|| Self::Custom(name.to_owned())))
+    }
+}
+
+impl fmt::Display for CsvField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CsvColumn {
+    type Err = String;
+
+    fn from_str(mapping: &str) -> Result<Self, Self::Err> {
+        let (field, column) = mapping.split_once('=').unwrap_or((mapping, mapping));
+        let (field, column) = (field.trim(), column.trim());
+        if field.is_empty() || column.is_empty() {
+            return Err(format!(
+                "Invalid column mapping `{mapping}`, must be `field=column` or `field`"
+            ));
+        }
+        Ok(Self {
+            field:  field.parse().expect("Infallible"),
+            column: column.to_owned(),
+        })
+    }
+}
+
+impl CsvPreset {
+    /// Returns the preset columns with their vault fields, `None` if the
+    /// column is ignored
+    const fn layout(self) -> &'static [(&'static str, Option<&'static str>)] {
+        match self {
+            Self::Chrome => {
+                &[
+                    ("name", Some("name")),
+                    ("url", Some("service")),
+                    ("username", Some("username")),
+                    ("password", Some("password")),
+                    ("note", Some("note")),
+                ]
+            }
+            Self::Firefox => {
+                &[
+                    ("url", Some("service")),
+                    ("username", Some("username")),
+                    ("password", Some("password")),
+                    ("httpRealm", None),
+                    ("formActionOrigin", None),
+                    ("guid", None),
+                    ("timeCreated", None),
+                    ("timeLastUsed", None),
+                    ("timePasswordChanged", None),
+                ]
+            }
+            Self::OnePassword => {
+                &[
+                    ("Title", Some("name")),
+                    ("Url", Some("service")),
+                    ("Username", Some("username")),
+                    ("Password", Some("password")),
+                    ("OTPAuth", Some("totp")),
+                    ("Favorite", None),
+                    ("Archived", None),
+                    ("Notes", Some("note")),
+                ]
+            }
+            Self::LastPass => {
+                &[
+                    ("url", Some("service")),
+                    ("username", Some("username")),
+                    ("password", Some("password")),
+                    ("totp", Some("totp")),
+                    ("extra", Some("note")),
+                    ("name", Some("name")),
+                    ("grouping", Some("folder")),
+                    ("fav", None),
+                ]
+            }
+        }
+    }
+}
+
+/// Returns the index of the column in the header, case-insensitive
+fn column_index(headers: &StringRecord, column: &str) -> Option<usize> {
+    headers
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(column))
+}
+
+/// Returns the assigned vault field of each column, the columns that are not
+/// in the returned set are unmapped columns.
+///
+/// The base assignment is the preset layout, or the columns with a vault field
+/// name if there is no preset. Then the mapping overrides the field columns.
+///
+/// ## Errors
+/// - If a mapped column is not in the header
+fn assign_columns(
+    headers: &StringRecord,
+    preset: Option<CsvPreset>,
+    mapping: &[CsvColumn],
+) -> LprsResult<(AssignedColumns, HashSet<usize>)> {
+    let mut assigned = Vec::new();
+    let mut mapped = HashSet::new();
+
+    if let Some(preset) = preset {
+        for (column, field) in preset.layout() {
+            if let Some(idx) = column_index(headers, column) {
+                mapped.insert(idx);
+                if let Some(field) = field {
+                    assigned.push((idx, field.parse().expect("Infallible")));
+                }
+            }
+        }
+    } else {
+        for field in VAULT_FIELDS {
+            if let Some(idx) = column_index(headers, field.as_str()) {
+                mapped.insert(idx);
+                assigned.push((idx, field));
+            }
+        }
+    }
+
+    for CsvColumn { field, column } in mapping {
+        let idx = column_index(headers, column).ok_or_else(|| {
+            LprsError::Csv(format!("The column `{column}` is not in the CSV header"))
+        })?;
+        assigned.retain(|(_, assigned_field)| assigned_field != field);
+        mapped.insert(idx);
+        assigned.push((idx, field.clone()));
+    }
+
+    Ok((assigned, mapped))
+}
+
+/// Convert the CSV record to a vault, the empty values are ignored and the
+/// unmapped columns are custom fields
+fn record_to_vault(
+    headers: &StringRecord,
+    record: &StringRecord,
+    assigned: &[(usize, CsvField)],
+    mapped: &HashSet<usize>,
+) -> LprsResult<Vault> {
+    let line = record.position().map_or(0, |p| p.line());
+    let value = |idx: usize| record.get(idx).filter(|v| !v.trim().is_empty());
+
+    let mut fields: BTreeMap<&str, String> = BTreeMap::new();
+    let mut custom_fields = BTreeMap::new();
+    for (idx, field) in assigned {
+        if let Some(value) = value(*idx) {
+            match field {
+                CsvField::Custom(key) => {
+                    custom_fields.insert(key.clone(), value.to_owned());
+                }
+                field => {
+                    fields.insert(field.as_str(), value.to_owned());
+                }
+            }
+        }
+    }
+    for (idx, header) in headers.iter().enumerate() {
+        if let Some(value) = value(idx).filter(|_| !mapped.contains(&idx)) {
+            custom_fields.insert(header.trim().to_owned(), value.to_owned());
+        }
+    }
+
+    let name = fields
+        .remove("name")
+        .or_else(|| fields.get("service").cloned())
+        .ok_or_else(|| {
+            LprsError::Csv(format!(
+                "The row in line {line} don't have a name nor a service"
+            ))
+        })?;
+    let (totp_secret, totp_hash, totp_digits) = match fields
+        .remove("totp")
+        .as_deref()
+        .map(otpauth::parse_totp_field)
+        .transpose()
+        .map_err(|err| LprsError::Csv(format!("The row in line {line} have invalid TOTP: {err}")))?
+        .flatten()
+    {
+        Some((secret, hash, digits)) => (Some(secret), hash, digits),
+        None => (None, TotpHash::default(), otpauth::DEFAULT_DIGITS),
+    };
+
+    Ok(Vault {
+        folder: fields.remove("folder"),
+        ..Vault::new(
+            name.trim(),
+            fields.remove("username"),
+            fields.remove("password"),
+            fields.remove("service"),
+            fields.remove("note"),
+            custom_fields,
+            totp_secret,
+            totp_hash,
+            totp_digits,
+        )
+    })
+}
+
+/// Parse the CSV data and returns its vaults. The first row must be the
+/// header.
+///
+/// The columns are assigned to the vault fields by the preset layout (or by
+/// the fields names if there is no preset), then by the given mapping. The
+/// unmapped columns will be custom fields, and the service will be the vault
+/// name if there is no name.
+///
+/// ## Errors
+/// - If the CSV is malformed
+/// - If a mapped column is not in the header
+/// - If a row don't have a name nor a service, or have an invalid TOTP
+pub fn import(
+    csv_data: &[u8],
+    preset: Option<CsvPreset>,
+    mapping: &[CsvColumn],
+) -> LprsResult<Vec<Vault>> {
+    // The spreadsheets may start the UTF-8 CSV with a BOM
+    let csv_data = csv_data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(csv_data);
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(csv_data);
+    let headers = reader
+        .headers()
+        .map_err(|err| LprsError::Csv(err.to_string()))?
+        .clone();
+    let (assigned, mapped) = assign_columns(&headers, preset, mapping)?;
+
+    reader
+        .records()
+        .map(|record| {
+            record_to_vault(
+                &headers,
+                &record.map_err(|err| LprsError::Csv(err.to_string()))?,
+                &assigned,
+                &mapped,
+            )
+        })
+        .collect()
+}
+
+/// Export the vaults as CSV with the given columns, or the preset layout
+/// columns if there is no columns.
+///
+/// Without columns nor preset, the columns will be the vault fields followed
+/// by all the custom fields. The typed items fields are exported as custom
+/// fields.
+///
+/// ## Errors
+/// - If can't write the CSV
+pub fn export(
+    vaults: &[Vault],
+    preset: Option<CsvPreset>,
+    columns: &[CsvColumn],
+) -> LprsResult<Vec<u8>> {
+    let vaults: Vec<Vault> = vaults.iter().map(Vault::flatten_item).collect();
+    let columns: Vec<(String, Option<CsvField>)> = if !columns.is_empty() {
+        columns
+            .iter()
+            .map(|c| (c.column.clone(), Some(c.field.clone())))
+            .collect()
+    } else if let Some(preset) = preset {
+        preset
+            .layout()
+            .iter()
+            .map(|(column, field)| {
+                (
+                    (*column).to_owned(),
+                    field.map(|f| f.parse().expect("Infallible")),
+                )
+            })
+            .collect()
+    } else {
+        let custom_keys: BTreeSet<&String> =
+            vaults.iter().flat_map(|v| v.custom_fields.keys()).collect();
+        VAULT_FIELDS
+            .into_iter()
+            .chain(custom_keys.into_iter().map(|k| CsvField::Custom(k.clone())))
+            .map(|field| (field.to_string(), Some(field)))
+            .collect()
+    };
+
+    let mut writer = WriterBuilder::new().from_writer(Vec::new());
+    let csv_err = |err: ::csv::Error| LprsError::Csv(err.to_string());
+    writer
+        .write_record(columns.iter().map(|(column, _)| column))
+        .map_err(csv_err)?;
+    for vault in &vaults {
+        writer
+            .write_record(columns.iter().map(|(_, field)| {
+                field
+                    .as_ref()
+                    .and_then(|f| f.value(vault))
+                    .unwrap_or_default()
+            }))
+            .map_err(csv_err)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| LprsError::Csv(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a vault with the given name, password and note
+    fn vault(name: &str, password: &str, note: Option<&str>) -> Vault {
+        Vault::new(
+            name,
+            Some("user"),
+            Some(password),
+            Some("https://example.com"),
+            note,
+            BTreeMap::new(),
+            None::<String>,
+            TotpHash::default(),
+            otpauth::DEFAULT_DIGITS,
+        )
+    }
+
+    #[test]
+    fn import_quoted_fields() {
+        let csv_data = "name,username,password,note\n\"Doe, John\",john,\"pa\"\"ss,word\",\"first \
+                        line\nsecond line\"\n";
+        let vaults = import(csv_data.as_bytes(), None, &[]).unwrap();
+        assert_eq!(vaults.len(), 1);
+        assert_eq!(vaults[0].name, "Doe, John");
+        assert_eq!(vaults[0].password.as_deref(), Some("pa\"ss,word"));
+        assert_eq!(vaults[0].note.as_deref(), Some("first line\nsecond line"));
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let vaults = [
+            vault("GitHub", "a,b\"c", Some("line 1\nline 2\n")),
+            vault("Mail", "p@ss", None),
+        ];
+        let csv_data = export(&vaults, None, &[]).unwrap();
+        assert_eq!(import(&csv_data, None, &[]).unwrap(), vaults);
+    }
+
+    #[test]
+    fn import_preset_and_mapping() {
+        let csv_data =
+            "name,url,username,password,note,pin\nBank,https://bank.com,me,secret,,1234\n";
+        let mapping = ["note=pin".parse::<CsvColumn>().unwrap()];
+        let vaults = import(csv_data.as_bytes(), Some(CsvPreset::Chrome), &mapping).unwrap();
+        assert_eq!(vaults[0].service.as_deref(), Some("https://bank.com"));
+        assert_eq!(vaults[0].note.as_deref(), Some("1234"));
+        assert!(vaults[0].custom_fields.is_empty());
+
+        let vaults = import(csv_data.as_bytes(), Some(CsvPreset::Chrome), &[]).unwrap();
+        assert_eq!(
+            vaults[0].custom_fields.get("pin").map(String::as_str),
+            Some("1234")
+        );
+    }
+
+    #[test]
+    fn import_without_name() {
+        // The service is the name if there is no name
+        let vaults = import(b"service,password\nexample.com,secret\n", None, &[]);
+        assert!(vaults.is_ok_and(|v| v[0].name == "example.com"));
+        assert!(import(b"username,password\nme,secret\n", None, &[]).is_err());
+        assert!(
+            import(
+                b"name,password\nme,secret\n",
+                None,
+                &["note=pin".parse().unwrap()]
+            )
+            .is_err()
+        );
+    }
+}
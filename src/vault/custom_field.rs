@@ -0,0 +1,153 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The kinds of the vault custom fields, they decide how the fields are
+//! prompted, shown, searched and exported
+
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use clap::ValueEnum;
+use inquire::{Text, validator::Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    LprsError,
+    LprsResult,
+    utils,
+    vault::{cipher, item},
+};
+
+/// The kind of a vault custom field, the fields without a kind are secrets
+#[derive(Default, Clone, Copy, Debug, ValueEnum, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    /// A plain text, shown and searchable
+    Text,
+    /// A secret, masked and never searched
+    #[default]
+    Secret,
+    /// A URL, e.g. `https://example.com`
+    Url,
+    /// An email address
+    Email,
+    /// A base32 TOTP secret, its current code is shown with it
+    Totp,
+    /// A multi-line text, e.g. the recovery codes
+    Multiline,
+}
+
+impl FieldKind {
+    /// Returns true if the field is a secret, it's masked in the TUI and the
+    /// diff, protected in the exports and never searched
+    pub const fn is_secret(self) -> bool {
+        matches!(self, Self::Secret | Self::Totp)
+    }
+
+    /// Check the field value of this kind
+    ///
+    /// ## Errors
+    /// - If the value is invalid for the kind, e.g. a URL without a scheme
+    pub fn check(self, key: &str, value: &str) -> LprsResult<()> {
+        let message = match self {
+            Self::Secret | Self::Multiline => return Ok(()),
+            Self::Text if !value.contains('\n') => return Ok(()),
+            Self::Text => "can't have a new line, make it a `multiline` field",
+            Self::Url if is_valid_url(value) => return Ok(()),
+            Self::Url => "is not a valid URL, e.g. `https://example.com`",
+            Self::Email if item::is_valid_email(value) => return Ok(()),
+            Self::Email => "is not a valid email address",
+            Self::Totp if cipher::base32_decode(value).is_ok() => return Ok(()),
+            Self::Totp => "is not a valid TOTP secret, must be valid base32 string",
+        };
+        Err(LprsError::InvalidCustomField(format!(
+            "The `{key}` field {message}"
+        )))
+    }
+
+    /// Returns the field value, the multi-line value `-` is read from the
+    /// stdin
+    ///
+    /// ## Errors
+    /// - If can't read the value from the stdin
+    pub fn read_value(self, value: String) -> LprsResult<String> {
+        if self != Self::Multiline || value != "-" {
+            return Ok(value);
+        }
+        log::info!("Reading the field value from the stdin");
+        let mut buf = Vec::new();
+        io::stdin().read_to_end(&mut buf)?;
+        String::from_utf8(buf).map_err(Into::into)
+    }
+
+    /// Ask the user for the field value. The secrets are masked, and the
+    /// multi-line text is read from the stdin until the end of the input
+    ///
+    /// ## Errors
+    /// - If can't read the user input
+    pub fn prompt(self, key: &str) -> LprsResult<String> {
+        let message = format!("Value of `{key}`:");
+        if self == Self::Multiline {
+            eprintln!("{message} (press Ctrl-D to finish)");
+            let value = self.read_value("-".to_owned())?;
+            if value.is_empty() {
+                return Err(LprsError::EmptyValue);
+            }
+            return Ok(value);
+        }
+
+        let field_key = key.to_owned();
+        let validator = move |value: &str| {
+            Ok(if value.is_empty() {
+                Validation::Invalid("The field value can't be empty".into())
+            } else if let Err(LprsError::InvalidCustomField(err)) = self.check(&field_key, value) {
+                Validation::Invalid(err.into())
+            } else {
+                Validation::Valid
+            })
+        };
+        if self.is_secret() {
+            utils::secret_prompt(&message, false, Some(vec![Box::new(validator)]))
+        } else {
+            Text::new(&message)
+                .with_validator(validator)
+                .prompt()
+                .map_err(Into::into)
+        }
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self
+            .to_possible_value()
+            .expect("There is no skipped field kind");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+/// Returns true if the URL have a scheme and the rest, `SCHEME://REST`
+fn is_valid_url(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, rest)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+            && !rest.is_empty()
+    }) && !url.contains(char::is_whitespace)
+}
@@ -0,0 +1,286 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    fmt,
+};
+
+use serde::Serialize;
+use sha2::Digest;
+
+use super::{
+    MASK,
+    Vault,
+    attachment::{Attachment, HumanSize},
+};
+
+/// The differences between two vaults lists, the vaults are matched by their
+/// names (the duplicated names are matched in order)
+#[derive(Debug, Default, Serialize)]
+pub struct VaultsDiff {
+    /// The vaults names that are only in the other vaults
+    pub added:    Vec<String>,
+    /// The vaults names that are only in the current vaults
+    pub removed:  Vec<String>,
+    /// The vaults that are in both with different fields
+    pub modified: Vec<ModifiedVault>,
+}
+
+/// A vault that have different fields
+#[derive(Debug, Serialize)]
+pub struct ModifiedVault {
+    /// The vault name
+    pub name:    String,
+    /// The changed fields
+    pub changes: Vec<FieldChange>,
+}
+
+/// A changed vault field
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    /// The field name, the custom fields are `custom_fields.<key>` with their
+    /// kinds `custom_field_kinds.<key>`, the attachments are
+    /// `attachments.<name>` and the item fields are `item.<field>`
+    pub field: String,
+    /// The field value in the current vault, `None` if it's not there
+    pub old:   Option<String>,
+    /// The field value in the other vault, `None` if it's not there
+    pub new:   Option<String>,
+}
+
+impl VaultsDiff {
+    /// Returns true if there is no differences
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Returns the comparable fields of the vault, with whether the field is a
+/// secret. The custom fields are secrets by their kinds, the item fields are
+/// `item.<field>`
+fn vault_fields(vault: &Vault) -> Vec<(String, Option<String>, bool)> {
+    let mut fields = vec![
+        (
+            "type".to_owned(),
+            Some(vault.item.item_type().to_string()),
+            false,
+        ),
+        ("username".to_owned(), vault.username.clone(), false),
+        ("password".to_owned(), vault.password.clone(), true),
+        ("service".to_owned(), vault.service.clone(), false),
+        ("note".to_owned(), vault.note.clone(), false),
+        ("folder".to_owned(), vault.folder.clone(), false),
+        ("totp_secret".to_owned(), vault.totp_secret.clone(), true),
+        (
+            "totp_hash".to_owned(),
+            vault
+                .totp_secret
+                .as_ref()
+                .map(|_| format!("{:?}", vault.totp_hash).to_uppercase()),
+            false,
+        ),
+        (
+            "totp_digits".to_owned(),
+            vault
+                .totp_secret
+                .as_ref()
+                .map(|_| vault.totp_digits.to_string()),
+            false,
+        ),
+        (
+            "policy".to_owned(),
+            vault.policy.as_ref().map(ToString::to_string),
+            false,
+        ),
+    ];
+    fields.extend(
+        vault
+            .item
+            .fields()
+            .into_iter()
+            .map(|(field, _, value, secret)| {
+                (
+                    format!("item.{}", field.name()),
+                    Some(value.to_owned()),
+                    secret,
+                )
+            }),
+    );
+    for (key, value) in &vault.custom_fields {
+        let kind = vault.field_kind(key);
+        fields.push((
+            format!("custom_fields.{key}"),
+            Some(value.clone()),
+            kind.is_secret(),
+        ));
+        fields.push((
+            format!("custom_field_kinds.{key}"),
+            Some(kind.to_string()),
+            false,
+        ));
+    }
+    fields.extend(vault.attachments.iter().map(|attachment| {
+        (
+            format!("attachments.{}", attachment.name),
+            Some(attachment_summary(attachment)),
+            false,
+        )
+    }));
+    fields
+}
+
+/// Returns the attachment MIME type and size, with the beginning of its
+/// content SHA-256 hash to see the content changes
+fn attachment_summary(attachment: &Attachment) -> String {
+    let hash = sha2::Sha256::digest(&attachment.data);
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&hash[..8]);
+    format!(
+        "{}, {}, sha256:{:016x}",
+        attachment.mime,
+        HumanSize(attachment.data.len()),
+        u64::from_be_bytes(prefix)
+    )
+}
+
+/// Returns true if the field is a custom field, an attachment or an item
+/// field, they are sorted after the vault fields
+fn is_keyed_field(name: &str) -> bool {
+    name.starts_with("custom_fields.")
+        || name.starts_with("custom_field_kinds.")
+        || name.starts_with("attachments.")
+        || name.starts_with("item.")
+}
+
+/// Returns the changed fields between the two vaults
+fn vault_changes(current: &Vault, other: &Vault, show_secrets: bool) -> Vec<FieldChange> {
+    let mask = |value: Option<String>, is_secret: bool| {
+        if is_secret && !show_secrets {
+            value.map(|_| MASK.to_owned())
+        } else {
+            value
+        }
+    };
+
+    let current_fields = vault_fields(current);
+    let other_fields = vault_fields(other);
+    // The vault fields in their order, then the attachments, the custom fields
+    // and the item fields sorted
+    let keyed_fields: BTreeSet<&String> = current_fields
+        .iter()
+        .chain(&other_fields)
+        .map(|(name, ..)| name)
+        .filter(|name| is_keyed_field(name))
+        .collect();
+    let field_names = current_fields
+        .iter()
+        .map(|(name, ..)| name)
+        .filter(|name| !is_keyed_field(name))
+        .chain(keyed_fields);
+
+    let find = |fields: &[(String, Option<String>, bool)], name: &str| {
+        fields
+            .iter()
+            .find(|(field, ..)| field == name)
+            .and_then(|(_, value, is_secret)| value.clone().map(|v| (v, *is_secret)))
+    };
+    field_names
+        .filter_map(|name| {
+            let old = find(&current_fields, name);
+            let new = find(&other_fields, name);
+            if old.as_ref().map(|(v, _)| v) == new.as_ref().map(|(v, _)| v) {
+                return None;
+            }
+            // A secret in one of the vaults, e.g. its kind is changed, is masked
+            let is_secret = old.iter().chain(&new).any(|(_, s)| *s);
+            Some(FieldChange {
+                field: name.clone(),
+                old:   mask(old.map(|(v, _)| v), is_secret),
+                new:   mask(new.map(|(v, _)| v), is_secret),
+            })
+        })
+        .collect()
+}
+
+/// Returns the differences from the current vaults to the other vaults, the
+/// secrets (passwords, TOTP secrets, secret custom fields and the item
+/// secrets) are masked unless `show_secrets` is true
+pub fn diff(current: &[Vault], other: &[Vault], show_secrets: bool) -> VaultsDiff {
+    let mut other_by_name: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (idx, vault) in other.iter().enumerate() {
+        other_by_name
+            .entry(vault.name.as_str())
+            .or_default()
+            .push_back(idx);
+    }
+
+    let mut vaults_diff = VaultsDiff::default();
+    let mut matched = vec![false; other.len()];
+    for vault in current {
+        match other_by_name
+            .get_mut(vault.name.as_str())
+            .and_then(VecDeque::pop_front)
+        {
+            Some(idx) => {
+                matched[idx] = true;
+                let changes = vault_changes(vault, &other[idx], show_secrets);
+                if !changes.is_empty() {
+                    vaults_diff.modified.push(ModifiedVault {
+                        name: vault.name.clone(),
+                        changes,
+                    });
+                }
+            }
+            None => vaults_diff.removed.push(vault.name.clone()),
+        }
+    }
+    vaults_diff.added = other
+        .iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(vault, _)| vault.name.clone())
+        .collect();
+
+    vaults_diff
+}
+
+impl fmt::Display for VaultsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The multiline values (e.g. the notes) are kept in a single line
+        let value = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map_or_else(|| "(none)".to_owned(), |v| v.replace('\n', "\\n"))
+        };
+
+        let mut lines = Vec::new();
+        lines.extend(self.added.iter().map(|name| format!("+ {name}")));
+        lines.extend(self.removed.iter().map(|name| format!("- {name}")));
+        for vault in &self.modified {
+            lines.push(format!("~ {}", vault.name));
+            lines.extend(vault.changes.iter().map(|change| {
+                format!(
+                    "    {}: {} -> {}",
+                    change.field,
+                    value(&change.old),
+                    value(&change.new)
+                )
+            }));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,332 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The Google Authenticator export is a `otpauth-migration://offline?data=`
+//! URI, where the data is a base64 protobuf `MigrationPayload` message:
+//!
+//! ```protobuf
+//! message MigrationPayload {
+//!   message OtpParameters {
+//!     bytes secret = 1;
+//!     string name = 2;
+//!     string issuer = 3;
+//!     Algorithm algorithm = 4; // 1: SHA1, 2: SHA256, 3: SHA512, 4: MD5
+//!     DigitCount digits = 5;   // 1: SIX, 2: EIGHT
+//!     OtpType type = 6;        // 1: HOTP, 2: TOTP
+//!     int64 counter = 7;
+//!   }
+//!   repeated OtpParameters otp_parameters = 1;
+//!   int32 version = 2;
+//!   int32 batch_size = 3;
+//!   int32 batch_index = 4;
+//!   int32 batch_id = 5;
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use base32::Alphabet as Base32Alphabet;
+use base64::{
+    Engine,
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use percent_encoding::percent_decode_str;
+
+use super::{Vault, cipher::TotpHash};
+use crate::{LprsError, LprsResult};
+
+/// The scheme of the Google Authenticator export URI
+const MIGRATION_SCHEME: &str = "otpauth-migration://";
+
+/// Base64 engine that accepts the data with or without the padding
+const BASE64_INDIFFERENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// A protobuf field value, only the wire types used by the migration payload
+enum WireValue<'a> {
+    /// A varint value (wire type 0)
+    Varint(u64),
+    /// A length-delimited value (wire type 2)
+    Bytes(&'a [u8]),
+}
+
+/// A single OTP entry of the migration payload
+#[derive(Default)]
+struct OtpParameters {
+    secret:    Vec<u8>,
+    name:      String,
+    issuer:    String,
+    algorithm: u64,
+    digits:    u64,
+    otp_type:  u64,
+}
+
+/// Returns an error of malformed migration payload
+fn malformed() -> LprsError {
+    LprsError::GoogleAuthMigration("Malformed migration payload".to_owned())
+}
+
+/// Read a protobuf varint from the start of the data and advance it
+fn read_varint(data: &mut &[u8]) -> LprsResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or_else(malformed)?;
+        *data = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(malformed())
+}
+
+/// Parse the protobuf message fields, returns the field numbers with their
+/// values. The fixed size fields are skipped because the payload don't use
+/// them.
+fn parse_fields(mut data: &[u8]) -> LprsResult<Vec<(u64, WireValue<'_>)>> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        let value = match key & 0b111 {
+            0 => WireValue::Varint(read_varint(&mut data)?),
+            1 | 5 => {
+                let len = if key & 0b111 == 1 { 8 } else { 4 };
+                data = data.get(len..).ok_or_else(malformed)?;
+                continue;
+            }
+            2 => {
+                let len = usize::try_from(read_varint(&mut data)?).map_err(|_| malformed())?;
+                let value = data.get(..len).ok_or_else(malformed)?;
+                data = &data[len..];
+                WireValue::Bytes(value)
+            }
+            _ => return Err(malformed()),
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+impl OtpParameters {
+    /// Decode the `OtpParameters` protobuf message
+    fn decode(data: &[u8]) -> LprsResult<Self> {
+        let to_string = |bytes: &[u8]| String::from_utf8(bytes.to_vec()).map_err(|_| malformed());
+
+        let mut params = Self::default();
+        for (number, value) in parse_fields(data)? {
+            match (number, value) {
+                (1, WireValue::Bytes(bytes)) => params.secret = bytes.to_vec(),
+                (2, WireValue::Bytes(bytes)) => params.name = to_string(bytes)?,
+                (3, WireValue::Bytes(bytes)) => params.issuer = to_string(bytes)?,
+                (4, WireValue::Varint(value)) => params.algorithm = value,
+                (5, WireValue::Varint(value)) => params.digits = value,
+                (6, WireValue::Varint(value)) => params.otp_type = value,
+                _ => {}
+            }
+        }
+        Ok(params)
+    }
+
+    /// Returns the display name of the entry, used in the skipping reasons
+    fn display_name(&self) -> &str {
+        if self.issuer.is_empty() {
+            &self.name
+        } else {
+            &self.issuer
+        }
+    }
+
+    /// Convert the entry to a vault, returns the reason if it's not supported
+    fn into_vault(self) -> Result<Vault, String> {
+        let totp_hash = match self.algorithm {
+            0 | 1 => TotpHash::Sha1,
+            2 => TotpHash::Sha256,
+            3 => TotpHash::Sha512,
+            _ => return Err("unsupported hash algorithm (MD5)".to_owned()),
+        };
+        let totp_digits = match self.digits {
+            0 | 1 => 6,
+            2 => 8,
+            _ => return Err("unsupported digit count".to_owned()),
+        };
+        if self.otp_type == 1 {
+            return Err("HOTP is not supported, only TOTP".to_owned());
+        }
+        if self.secret.is_empty() {
+            return Err("there is no secret".to_owned());
+        }
+
+        // The name can be `issuer:account` or just the account
+        let (label_issuer, account) = match self.name.split_once(':') {
+            Some((issuer, account)) => (issuer.trim(), account.trim()),
+            None => ("", self.name.trim()),
+        };
+        let issuer = if self.issuer.trim().is_empty() {
+            label_issuer
+        } else {
+            self.issuer.trim()
+        };
+        let name = if issuer.is_empty() { account } else { issuer };
+        if name.is_empty() {
+            return Err("there is no name nor issuer".to_owned());
+        }
+
+        Ok(Vault::new(
+            name,
+            (!account.is_empty() && account != name).then_some(account),
+            None::<String>,
+            None::<String>,
+            None::<String>,
+            BTreeMap::new(),
+            Some(base32::encode(
+                Base32Alphabet::Rfc4648 { padding: false },
+                &self.secret,
+            )),
+            totp_hash,
+            totp_digits,
+        ))
+    }
+}
+
+/// Decode a single `otpauth-migration://` URI, returns its vaults and the
+/// reasons of the skipped entries
+fn decode_uri(uri: &str) -> LprsResult<(Vec<Vault>, Vec<String>)> {
+    let data = uri
+        .strip_prefix(MIGRATION_SCHEME)
+        .and_then(|rest| rest.split_once('?'))
+        .and_then(|(_, query)| query.split('&').find_map(|kv| kv.strip_prefix("data=")))
+        .ok_or_else(|| {
+            LprsError::GoogleAuthMigration("The URI don't have a `data` parameter".to_owned())
+        })?;
+    let payload = BASE64_INDIFFERENT.decode(
+        percent_decode_str(data)
+            .decode_utf8()
+            .map_err(|_| malformed())?
+            .as_bytes(),
+    )?;
+
+    let mut vaults = Vec::new();
+    let mut skipped = Vec::new();
+    for (number, value) in parse_fields(&payload)? {
+        if let (1, WireValue::Bytes(bytes)) = (number, value) {
+            let params = OtpParameters::decode(bytes)?;
+            let name = params.display_name().to_owned();
+            match params.into_vault() {
+                Ok(vault) => vaults.push(vault),
+                Err(reason) => skipped.push(format!("`{name}`: {reason}")),
+            }
+        }
+    }
+    Ok((vaults, skipped))
+}
+
+/// Decode all the `otpauth-migration://` URIs in the given text, returns
+/// their vaults and the reasons of the skipped entries (unsupported entries).
+///
+/// The text can contain multiple URIs separated by whitespace, Google
+/// Authenticator splits the large exports into multiple QR codes.
+///
+/// ## Errors
+/// - If there is no `otpauth-migration://` URI in the text
+/// - If the URI data is not valid base64 or malformed protobuf
+pub fn migration_vaults(text: &str) -> LprsResult<(Vec<Vault>, Vec<String>)> {
+    let mut vaults = Vec::new();
+    let mut skipped = Vec::new();
+    let mut found_uri = false;
+    for uri in text
+        .split_whitespace()
+        .filter(|s| s.starts_with(MIGRATION_SCHEME))
+    {
+        found_uri = true;
+        let (uri_vaults, uri_skipped) = decode_uri(uri)?;
+        vaults.extend(uri_vaults);
+        skipped.extend(uri_skipped);
+    }
+
+    if !found_uri {
+        return Err(LprsError::GoogleAuthMigration(format!(
+            "There is no `{MIGRATION_SCHEME}` URI in the given data"
+        )));
+    }
+    Ok((vaults, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A migration URI of three entries: `GitHub:alice` (SHA1, 6 digits),
+    /// `bob@example.com` (SHA256, 8 digits) and `Counter` (HOTP)
+    const MIGRATION_URI: &str = "otpauth-migration://offline?data=\
+                                 CigKCkhlbGxvId6tvu8SDEdpdEh1YjphbGljZRoGR2l0SHViIAEoATACCi0KFDEy\
+                                 MzQ1Njc4OTAxMjM0NTY3ODkwEg9ib2JAZXhhbXBsZS5jb20gAigCMAIKJQoUMTIz\
+                                 NDU2Nzg5MDEyMzQ1Njc4OTASB0NvdW50ZXIgASgBMAEQARgB";
+
+    #[test]
+    fn decode_known_uri() {
+        let (vaults, skipped) = migration_vaults(MIGRATION_URI).unwrap();
+        assert_eq!(vaults.len(), 2);
+
+        assert_eq!(vaults[0].name, "GitHub");
+        assert_eq!(vaults[0].username.as_deref(), Some("alice"));
+        assert_eq!(vaults[0].totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(vaults[0].totp_hash, TotpHash::Sha1);
+        assert_eq!(vaults[0].totp_digits, 6);
+
+        assert_eq!(vaults[1].name, "bob@example.com");
+        assert_eq!(vaults[1].username, None);
+        assert_eq!(
+            vaults[1].totp_secret.as_deref(),
+            Some("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")
+        );
+        assert_eq!(vaults[1].totp_hash, TotpHash::Sha256);
+        assert_eq!(vaults[1].totp_digits, 8);
+
+        assert_eq!(skipped, ["`Counter`: HOTP is not supported, only TOTP"]);
+    }
+
+    #[test]
+    fn decode_multiple_percent_encoded_uris() {
+        // The padding of the second URI data is percent encoded
+        let text = "otpauth-migration://offline?data=ChkKCkhlbGxvId6tvu8SBUVtYWlsIAMoATAC \
+                    otpauth-migration://offline?data=ChgKCkhlbGxvId6tvu8SBE1haWwgAygBMAI%3D";
+        let (vaults, skipped) = migration_vaults(text).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(
+            vaults.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+            ["Email", "Mail"]
+        );
+        assert!(vaults.iter().all(|v| v.totp_hash == TotpHash::Sha512));
+    }
+
+    #[test]
+    fn no_migration_uri() {
+        assert!(matches!(
+            migration_vaults("otpauth://totp/GitHub?secret=JBSWY3DPEHPK3PXP"),
+            Err(LprsError::GoogleAuthMigration(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_payload() {
+        // A length-delimited field longer than the data
+        assert!(migration_vaults("otpauth-migration://offline?data=Cig=").is_err());
+        assert!(migration_vaults("otpauth-migration://offline?secret=abc").is_err());
+    }
+}
@@ -0,0 +1,1138 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The typed vault items, the cards, identities, SSH keys and API tokens with
+//! their validated fields
+
+use std::{
+    borrow::Cow,
+    fmt,
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use base64::Engine;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::{LprsError, LprsResult, utils};
+
+/// The SSH private key field name, as passed to [`Item::map_fields`]
+const PRIVATE_KEY_FIELD: &str = "SSH private key";
+
+/// The vault item types
+#[derive(Default, Clone, Copy, Debug, ValueEnum, Eq, PartialEq)]
+pub enum ItemType {
+    /// A login, the username, password and service
+    #[default]
+    Login,
+    /// A secure note
+    Note,
+    /// A payment card, with its number, holder, expiry and security code
+    Card,
+    /// An identity, with the full name, email, phone and address
+    Identity,
+    /// An SSH key pair
+    SshKey,
+    /// An API token, with its expiry date
+    ApiToken,
+}
+
+/// The vault item, the [`ItemType`] with its fields
+#[derive(Default, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Item {
+    /// A login, its fields are the vault username, password and service
+    #[default]
+    Login,
+    /// A secure note, its content is the vault note
+    Note,
+    /// A payment card
+    Card(Card),
+    /// An identity
+    Identity(Identity),
+    /// An SSH key pair
+    SshKey(SshKey),
+    /// An API token
+    ApiToken(ApiToken),
+}
+
+/// The payment card fields
+#[derive(Default, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Card {
+    /// The card number, digits only
+    pub number: String,
+    /// The card holder name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+    /// The card expiry, `MM/YYYY`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<String>,
+    /// The card security code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvv:    Option<String>,
+}
+
+/// The identity fields, one of them at least is required
+#[derive(Default, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Identity {
+    /// The full name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_name: Option<String>,
+    /// The email address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email:     Option<String>,
+    /// The phone number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone:     Option<String>,
+    /// The postal address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address:   Option<String>,
+}
+
+/// The SSH key pair fields
+#[derive(Default, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SshKey {
+    /// The PEM private key, as it is in its file
+    pub private_key: String,
+    /// The OpenSSH public key, `ALGORITHM BASE64 [COMMENT]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key:  Option<String>,
+}
+
+/// The API token fields
+#[derive(Default, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ApiToken {
+    /// The token
+    pub token:  String,
+    /// The token expiry date, `YYYY-MM-DD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<String>,
+}
+
+/// The fields of the typed items, as named in the `get` command
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ItemField {
+    /// The card number
+    Number,
+    /// The card holder
+    Holder,
+    /// The card or the API token expiry
+    Expiry,
+    /// The card security code
+    Cvv,
+    /// The card brand, detected from its number
+    Brand,
+    /// The identity full name
+    FullName,
+    /// The identity email
+    Email,
+    /// The identity phone
+    Phone,
+    /// The identity address
+    Address,
+    /// The SSH private key
+    PrivateKey,
+    /// The SSH public key
+    PublicKey,
+    /// The SSH public key fingerprint
+    Fingerprint,
+    /// The API token
+    Token,
+}
+
+/// The item options of the `add` and `edit` commands
+#[derive(Debug, Args)]
+pub struct ItemArgs {
+    /// The vault item type, the fields of the other types can't be used with it
+    #[arg(long = "type", value_name = "TYPE", value_enum)]
+    pub item_type:    Option<ItemType>,
+    /// The card number, if there is no value you will prompt it
+    #[arg(long, value_name = "NUMBER")]
+    #[allow(clippy::option_option)]
+    pub card_number:  Option<Option<String>>,
+    /// The card holder name, make it empty string to delete it
+    #[arg(long, value_name = "NAME")]
+    pub card_holder:  Option<String>,
+    /// The card expiry, `MM/YY` or `MM/YYYY`. Make it empty string to delete it
+    #[arg(long, value_name = "MM/YY")]
+    pub card_expiry:  Option<String>,
+    /// The card security code, if there is no value you will prompt it. Make it
+    /// empty string to delete it
+    #[arg(long, value_name = "CVV")]
+    #[allow(clippy::option_option)]
+    pub card_cvv:     Option<Option<String>>,
+    /// The identity full name, make it empty string to delete it
+    #[arg(long, value_name = "NAME")]
+    pub full_name:    Option<String>,
+    /// The identity email, make it empty string to delete it
+    #[arg(long)]
+    pub email:        Option<String>,
+    /// The identity phone number, make it empty string to delete it
+    #[arg(long)]
+    pub phone:        Option<String>,
+    /// The identity postal address, make it empty string to delete it
+    #[arg(long)]
+    pub address:      Option<String>,
+    /// Read the SSH private key from the given file, `-` to read it from the
+    /// stdin
+    #[arg(long, value_name = "PATH")]
+    pub private_key:  Option<PathBuf>,
+    /// Read the SSH public key from the given file, e.g.
+    /// `~/.ssh/id_ed25519.pub`
+    #[arg(long, value_name = "PATH")]
+    pub public_key:   Option<PathBuf>,
+    /// The API token, if there is no value you will prompt it
+    #[arg(long, value_name = "TOKEN")]
+    #[allow(clippy::option_option)]
+    pub token:        Option<Option<String>>,
+    /// The API token expiry date, `YYYY-MM-DD`. Make it empty string to delete
+    /// it
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub token_expiry: Option<String>,
+}
+
+impl ItemType {
+    /// Returns the type name, as shown to the user
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Login => "Login",
+            Self::Note => "Secure note",
+            Self::Card => "Card",
+            Self::Identity => "Identity",
+            Self::SshKey => "SSH key",
+            Self::ApiToken => "API token",
+        }
+    }
+}
+
+impl fmt::Display for ItemType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self
+            .to_possible_value()
+            .expect("There is no skipped item type");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+impl Item {
+    /// Returns true if the item is a login, the default item
+    pub fn is_login(&self) -> bool {
+        self == &Self::Login
+    }
+
+    /// Returns the item type
+    pub const fn item_type(&self) -> ItemType {
+        match self {
+            Self::Login => ItemType::Login,
+            Self::Note => ItemType::Note,
+            Self::Card(_) => ItemType::Card,
+            Self::Identity(_) => ItemType::Identity,
+            Self::SshKey(_) => ItemType::SshKey,
+            Self::ApiToken(_) => ItemType::ApiToken,
+        }
+    }
+
+    /// Returns an empty item of the given type, its required fields are empty
+    fn empty(item_type: ItemType) -> Self {
+        match item_type {
+            ItemType::Login => Self::Login,
+            ItemType::Note => Self::Note,
+            ItemType::Card => Self::Card(Card::default()),
+            ItemType::Identity => Self::Identity(Identity::default()),
+            ItemType::SshKey => Self::SshKey(SshKey::default()),
+            ItemType::ApiToken => Self::ApiToken(ApiToken::default()),
+        }
+    }
+
+    /// Returns the item with its fields in their canonical form, e.g. the card
+    /// number without spaces and the `MM/YYYY` card expiry. The empty
+    /// optional fields are removed
+    ///
+    /// ## Errors
+    /// - If the item is invalid, see [`Item::validate`]
+    pub fn normalized(self) -> LprsResult<Self> {
+        let item = self
+            .map_fields(|field, value| {
+                if value.trim().is_empty() {
+                    return None;
+                }
+                // The private key is kept as it is, with its trailing newline
+                Some(
+                    if field == PRIVATE_KEY_FIELD {
+                        value.to_owned()
+                    } else {
+                        value.trim().to_owned()
+                    },
+                )
+            })
+            .ok_or_else(|| invalid("The item required fields can't be empty"))?;
+        let item = match item {
+            Self::Card(card) => {
+                Self::Card(Card {
+                    number: check_card_number(&card.number)?,
+                    expiry: card.expiry.as_deref().map(parse_card_expiry).transpose()?,
+                    ..card
+                })
+            }
+            item => item,
+        };
+        item.validate()?;
+        Ok(item)
+    }
+
+    /// Validate the item fields
+    ///
+    /// ## Errors
+    /// - If a required field is empty
+    /// - If a field is invalid, e.g. a card number with a wrong check digit
+    pub fn validate(&self) -> LprsResult<()> {
+        match self {
+            Self::Login | Self::Note => {}
+            Self::Card(card) => {
+                check_card_number(&card.number)?;
+                if let Some(ref expiry) = card.expiry {
+                    if parse_card_expiry(expiry)? != *expiry {
+                        return Err(invalid("The card expiry must be `MM/YYYY`"));
+                    }
+                }
+                if let Some(ref cvv) = card.cvv {
+                    check_cvv(cvv)?;
+                }
+            }
+            Self::Identity(identity) => {
+                if identity.full_name.is_none()
+                    && identity.email.is_none()
+                    && identity.phone.is_none()
+                    && identity.address.is_none()
+                {
+                    return Err(invalid(
+                        "The identity must have a full name, email, phone or address",
+                    ));
+                }
+                if let Some(ref email) = identity.email {
+                    check_email(email)?;
+                }
+                if let Some(ref phone) = identity.phone {
+                    check_phone(phone)?;
+                }
+            }
+            Self::SshKey(ssh_key) => {
+                check_private_key(&ssh_key.private_key)?;
+                if let Some(ref public_key) = ssh_key.public_key {
+                    public_key_blob(public_key)?;
+                }
+            }
+            Self::ApiToken(token) => {
+                if token.token.trim().is_empty() {
+                    return Err(invalid("The API token can't be empty"));
+                }
+                if let Some(ref expiry) = token.expiry {
+                    parse_date(expiry)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the item field value, `None` if the item doesn't have it
+    pub fn field(&self, field: ItemField) -> Option<Cow<'_, str>> {
+        fn borrowed(value: &Option<String>) -> Option<Cow<'_, str>> {
+            value.as_deref().map(Cow::Borrowed)
+        }
+
+        match (self, field) {
+            (Self::Card(card), ItemField::Number) => Some(Cow::Borrowed(&card.number)),
+            (Self::Card(card), ItemField::Holder) => borrowed(&card.holder),
+            (Self::Card(card), ItemField::Expiry) => borrowed(&card.expiry),
+            (Self::Card(card), ItemField::Cvv) => borrowed(&card.cvv),
+            (Self::Card(card), ItemField::Brand) => card_brand(&card.number).map(Cow::Borrowed),
+            (Self::Identity(identity), ItemField::FullName) => borrowed(&identity.full_name),
+            (Self::Identity(identity), ItemField::Email) => borrowed(&identity.email),
+            (Self::Identity(identity), ItemField::Phone) => borrowed(&identity.phone),
+            (Self::Identity(identity), ItemField::Address) => borrowed(&identity.address),
+            (Self::SshKey(ssh_key), ItemField::PrivateKey) => {
+                Some(Cow::Borrowed(&ssh_key.private_key))
+            }
+            (Self::SshKey(ssh_key), ItemField::PublicKey) => borrowed(&ssh_key.public_key),
+            (Self::SshKey(ssh_key), ItemField::Fingerprint) => {
+                ssh_key.fingerprint().map(Cow::Owned)
+            }
+            (Self::ApiToken(token), ItemField::Token) => Some(Cow::Borrowed(&token.token)),
+            (Self::ApiToken(token), ItemField::Expiry) => borrowed(&token.expiry),
+            _ => None,
+        }
+    }
+
+    /// Returns the stored fields of the item, with their labels and whether
+    /// they are secrets
+    pub fn fields(&self) -> Vec<(ItemField, &'static str, &str, bool)> {
+        let fields = match self {
+            Self::Login | Self::Note => vec![],
+            Self::Card(card) => {
+                vec![
+                    (
+                        ItemField::Holder,
+                        "Card Holder",
+                        card.holder.as_ref(),
+                        false,
+                    ),
+                    (ItemField::Number, "Card Number", Some(&card.number), true),
+                    (
+                        ItemField::Expiry,
+                        "Card Expiry",
+                        card.expiry.as_ref(),
+                        false,
+                    ),
+                    (ItemField::Cvv, "Card CVV", card.cvv.as_ref(), true),
+                ]
+            }
+            Self::Identity(identity) => {
+                vec![
+                    (
+                        ItemField::FullName,
+                        "Full Name",
+                        identity.full_name.as_ref(),
+                        false,
+                    ),
+                    (ItemField::Email, "Email", identity.email.as_ref(), false),
+                    (ItemField::Phone, "Phone", identity.phone.as_ref(), false),
+                    (
+                        ItemField::Address,
+                        "Address",
+                        identity.address.as_ref(),
+                        false,
+                    ),
+                ]
+            }
+            Self::SshKey(ssh_key) => {
+                vec![
+                    (
+                        ItemField::PublicKey,
+                        "Public Key",
+                        ssh_key.public_key.as_ref(),
+                        false,
+                    ),
+                    (
+                        ItemField::PrivateKey,
+                        "Private Key",
+                        Some(&ssh_key.private_key),
+                        true,
+                    ),
+                ]
+            }
+            Self::ApiToken(token) => {
+                vec![
+                    (ItemField::Token, "API Token", Some(&token.token), true),
+                    (
+                        ItemField::Expiry,
+                        "Token Expiry",
+                        token.expiry.as_ref(),
+                        false,
+                    ),
+                ]
+            }
+        };
+        fields
+            .into_iter()
+            .filter_map(|(field, label, value, secret)| {
+                value.map(|value| (field, label, value.as_str(), secret))
+            })
+            .collect()
+    }
+
+    /// Returns the fields to show to the user, with their labels and whether
+    /// they are secrets. Unlike [`Item::fields`] the values are formatted, and
+    /// there are the computed fields, e.g. the card brand and whether it's
+    /// expired
+    pub fn display_fields(&self) -> Vec<(&'static str, String, bool)> {
+        let mut fields: Vec<_> = self
+            .fields()
+            .into_iter()
+            .map(|(field, label, value, secret)| {
+                let value = match (self, field) {
+                    (Self::Card(_), ItemField::Number) => group_card_number(value),
+                    (Self::Card(card), ItemField::Expiry) if card.is_expired() => {
+                        format!("{value} (expired)")
+                    }
+                    (Self::ApiToken(token), ItemField::Expiry) if token.is_expired() => {
+                        format!("{value} (expired)")
+                    }
+                    _ => value.to_owned(),
+                };
+                (label, value, secret)
+            })
+            .collect();
+        match self {
+            Self::Card(card) => {
+                if let Some(brand) = card_brand(&card.number) {
+                    fields.insert(
+                        usize::from(card.holder.is_some()) + 1,
+                        ("Card Brand", brand.to_owned(), false),
+                    );
+                }
+            }
+            Self::SshKey(ssh_key) => {
+                if let Some(fingerprint) = ssh_key.fingerprint() {
+                    fields.insert(1, ("Fingerprint", fingerprint, false));
+                }
+            }
+            _ => {}
+        }
+        fields
+    }
+
+    /// Map the item fields, e.g. to encrypt or decrypt them. The optional
+    /// fields that mapped to `None` are removed, returns `None` if a required
+    /// field mapped to `None`
+    pub fn map_fields(&self, mut map: impl FnMut(&str, &str) -> Option<String>) -> Option<Self> {
+        let mut map_opt = |field: &str, value: &Option<String>| {
+            value.as_deref().and_then(|value| map(field, value))
+        };
+        Some(match self {
+            Self::Login => Self::Login,
+            Self::Note => Self::Note,
+            Self::Card(card) => {
+                Self::Card(Card {
+                    holder: map_opt("card holder", &card.holder),
+                    expiry: map_opt("card expiry", &card.expiry),
+                    cvv:    map_opt("card CVV", &card.cvv),
+                    number: map_opt("card number", &Some(card.number.clone()))?,
+                })
+            }
+            Self::Identity(identity) => {
+                Self::Identity(Identity {
+                    full_name: map_opt("full name", &identity.full_name),
+                    email:     map_opt("email", &identity.email),
+                    phone:     map_opt("phone", &identity.phone),
+                    address:   map_opt("address", &identity.address),
+                })
+            }
+            Self::SshKey(ssh_key) => {
+                Self::SshKey(SshKey {
+                    public_key:  map_opt("SSH public key", &ssh_key.public_key),
+                    private_key: map_opt(PRIVATE_KEY_FIELD, &Some(ssh_key.private_key.clone()))?,
+                })
+            }
+            Self::ApiToken(token) => {
+                Self::ApiToken(ApiToken {
+                    expiry: map_opt("API token expiry", &token.expiry),
+                    token:  map_opt("API token", &Some(token.token.clone()))?,
+                })
+            }
+        })
+    }
+}
+
+impl Card {
+    /// Returns true if the card expiry month is over
+    pub fn is_expired(&self) -> bool {
+        let Some((month, year)) = self.expiry.as_deref().and_then(|e| e.split_once('/')) else {
+            return false;
+        };
+        let (Ok(month), Ok(year)) = (month.parse::<u32>(), year.parse::<i64>()) else {
+            return false;
+        };
+        let (today_year, today_month, _) = today();
+        (year, month) < (today_year, today_month)
+    }
+}
+
+impl SshKey {
+    /// Returns the SHA256 fingerprint of the public key, as shown by
+    /// `ssh-keygen -l`
+    pub fn fingerprint(&self) -> Option<String> {
+        let blob = public_key_blob(self.public_key.as_deref()?).ok()?;
+        Some(format!(
+            "SHA256:{}",
+            base64::engine::general_purpose::STANDARD_NO_PAD.encode(sha2::Sha256::digest(blob))
+        ))
+    }
+}
+
+impl ApiToken {
+    /// Returns true if the expiry date is passed
+    pub fn is_expired(&self) -> bool {
+        self.expiry
+            .as_deref()
+            .and_then(|expiry| parse_date(expiry).ok())
+            .is_some_and(|date| date < today())
+    }
+}
+
+impl ItemField {
+    /// All the item fields
+    pub const ALL: [Self; 13] = [
+        Self::Number,
+        Self::Holder,
+        Self::Expiry,
+        Self::Cvv,
+        Self::Brand,
+        Self::FullName,
+        Self::Email,
+        Self::Phone,
+        Self::Address,
+        Self::PrivateKey,
+        Self::PublicKey,
+        Self::Fingerprint,
+        Self::Token,
+    ];
+
+    /// Returns the field name
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Number => "number",
+            Self::Holder => "holder",
+            Self::Expiry => "expiry",
+            Self::Cvv => "cvv",
+            Self::Brand => "brand",
+            Self::FullName => "full_name",
+            Self::Email => "email",
+            Self::Phone => "phone",
+            Self::Address => "address",
+            Self::PrivateKey => "private_key",
+            Self::PublicKey => "public_key",
+            Self::Fingerprint => "fingerprint",
+            Self::Token => "token",
+        }
+    }
+
+    /// Returns the field by its name, case insensitive
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl ItemArgs {
+    /// Returns true if there is no item option
+    pub fn is_empty(&self) -> bool {
+        self.item_type.is_none() && self.used_options().is_empty()
+    }
+
+    /// Returns true if there is an item field option, or a type with required
+    /// fields
+    pub fn has_fields(&self) -> bool {
+        !self.used_options().is_empty()
+            || self
+                .item_type
+                .is_some_and(|t| !matches!(t, ItemType::Login | ItemType::Note))
+    }
+
+    /// Returns the used item options with their item type
+    fn used_options(&self) -> Vec<(&'static str, ItemType)> {
+        [
+            ("--card-number", ItemType::Card, self.card_number.is_some()),
+            ("--card-holder", ItemType::Card, self.card_holder.is_some()),
+            ("--card-expiry", ItemType::Card, self.card_expiry.is_some()),
+            ("--card-cvv", ItemType::Card, self.card_cvv.is_some()),
+            ("--full-name", ItemType::Identity, self.full_name.is_some()),
+            ("--email", ItemType::Identity, self.email.is_some()),
+            ("--phone", ItemType::Identity, self.phone.is_some()),
+            ("--address", ItemType::Identity, self.address.is_some()),
+            (
+                "--private-key",
+                ItemType::SshKey,
+                self.private_key.is_some(),
+            ),
+            ("--public-key", ItemType::SshKey, self.public_key.is_some()),
+            ("--token", ItemType::ApiToken, self.token.is_some()),
+            (
+                "--token-expiry",
+                ItemType::ApiToken,
+                self.token_expiry.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(option, item_type, used)| used.then_some((option, item_type)))
+        .collect()
+    }
+
+    /// Check that the options are of the given item type
+    fn check_options(&self, item_type: ItemType) -> LprsResult<()> {
+        if let Some((option, option_type)) = self
+            .used_options()
+            .into_iter()
+            .find(|(_, option_type)| *option_type != item_type)
+        {
+            return Err(LprsError::ArgsConflict(format!(
+                "The `{option}` option is for the `{option_type}` items, the vault is \
+                 `{item_type}`. Use `--type {option_type}` to change its type"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validate the options, before prompting the secrets
+    ///
+    /// ## Errors
+    /// - If there is an option of another item type than `--type`
+    /// - If a file doesn't exist
+    pub fn validate(&self) -> LprsResult<()> {
+        if let Some(item_type) = self.item_type {
+            self.check_options(item_type)?;
+        }
+        for path in [&self.private_key, &self.public_key]
+            .into_iter()
+            .flatten()
+            .filter(|p| p.as_os_str() != "-")
+        {
+            // Not only the regular files, e.g. `<(command)`
+            if !path.exists() {
+                return Err(LprsError::Io(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("file `{}` not found", path.display()),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the options to the item, prompting the secrets that don't have a
+    /// value. The empty values delete the optional fields, then the item is
+    /// normalized, see [`Item::normalized`]
+    ///
+    /// ## Errors
+    /// - If there is an option of another item type
+    /// - If a required field is missing or empty
+    /// - If a field is invalid
+    /// - If reading a key file or prompting a secret failed
+    pub fn apply(self, item: &mut Item) -> LprsResult<()> {
+        let item_type = self.item_type.unwrap_or(item.item_type());
+        self.check_options(item_type)?;
+        let mut new_item = if item_type == item.item_type() {
+            item.clone()
+        } else {
+            Item::empty(item_type)
+        };
+        let optional = |value: String| (!value.is_empty()).then_some(value);
+
+        match new_item {
+            Item::Login | Item::Note => {}
+            Item::Card(ref mut card) => {
+                if let Some(number) = utils::user_secret(self.card_number, "Card number:", false)? {
+                    card.number = number;
+                }
+                if let Some(holder) = self.card_holder {
+                    card.holder = optional(holder);
+                }
+                if let Some(expiry) = self.card_expiry {
+                    card.expiry = optional(expiry);
+                }
+                if let Some(cvv) = utils::user_secret(self.card_cvv, "Card CVV:", false)? {
+                    card.cvv = optional(cvv);
+                }
+                if card.number.trim().is_empty() {
+                    return Err(invalid("The card number is required, use `--card-number`"));
+                }
+            }
+            Item::Identity(ref mut identity) => {
+                for (field, value) in [
+                    (&mut identity.full_name, self.full_name),
+                    (&mut identity.email, self.email),
+                    (&mut identity.phone, self.phone),
+                    (&mut identity.address, self.address),
+                ] {
+                    if let Some(value) = value {
+                        *field = optional(value);
+                    }
+                }
+            }
+            Item::SshKey(ref mut ssh_key) => {
+                if let Some(ref path) = self.private_key {
+                    ssh_key.private_key = read_key(path)?;
+                }
+                if let Some(ref path) = self.public_key {
+                    ssh_key.public_key = optional(read_key(path)?);
+                }
+                if ssh_key.private_key.trim().is_empty() {
+                    return Err(invalid(
+                        "The SSH private key is required, use `--private-key`",
+                    ));
+                }
+            }
+            Item::ApiToken(ref mut token) => {
+                if let Some(value) = utils::user_secret(self.token, "API token:", false)? {
+                    token.token = value;
+                }
+                if let Some(expiry) = self.token_expiry {
+                    token.expiry = optional(expiry);
+                }
+                if token.token.trim().is_empty() {
+                    return Err(invalid("The API token is required, use `--token`"));
+                }
+            }
+        }
+
+        *item = new_item.normalized()?;
+        Ok(())
+    }
+}
+
+/// Returns an invalid item error
+fn invalid(message: &str) -> LprsError {
+    LprsError::InvalidItem(message.to_owned())
+}
+
+/// Read the key file, `-` to read it from the stdin
+fn read_key(path: &PathBuf) -> LprsResult<String> {
+    let mut key = String::new();
+    if path.as_os_str() == "-" {
+        io::stdin().read_to_string(&mut key)?;
+    } else {
+        key = fs::read_to_string(path)?;
+    }
+    Ok(key)
+}
+
+/// Check the card number with the Luhn algorithm, returns it without the
+/// spaces and dashes
+///
+/// ## Errors
+/// - If the number is not 12 to 19 digits, or its check digit is wrong
+fn check_card_number(number: &str) -> LprsResult<String> {
+    let number: String = number
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if !(12..=19).contains(&number.len()) || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("The card number must be 12 to 19 digits"));
+    }
+
+    let sum: u32 = number
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(idx, b)| {
+            let digit = u32::from(b - b'0');
+            match (idx % 2 == 1, digit * 2) {
+                (false, _) => digit,
+                (true, doubled) if doubled > 9 => doubled - 9,
+                (true, doubled) => doubled,
+            }
+        })
+        .sum();
+    if sum % 10 != 0 {
+        return Err(invalid(
+            "The card number is invalid, its check digit is wrong",
+        ));
+    }
+    Ok(number)
+}
+
+/// Parse the card expiry, `MM/YY` or `MM/YYYY`, returns it as `MM/YYYY`
+///
+/// ## Errors
+/// - If the expiry is not a valid month
+fn parse_card_expiry(expiry: &str) -> LprsResult<String> {
+    let error = || invalid("The card expiry must be `MM/YY` or `MM/YYYY`");
+    let (month, year) = expiry.trim().split_once('/').ok_or_else(error)?;
+    let month: u32 = month.parse().map_err(|_| error())?;
+    if !(1..=12).contains(&month) || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(error());
+    }
+    let year: u32 = match year.len() {
+        2 => 2000 + year.parse::<u32>().map_err(|_| error())?,
+        4 => year.parse().map_err(|_| error())?,
+        _ => return Err(error()),
+    };
+    Ok(format!("{month:02}/{year}"))
+}
+
+/// Check the card security code, 3 or 4 digits
+fn check_cvv(cvv: &str) -> LprsResult<()> {
+    if !(3..=4).contains(&cvv.len()) || !cvv.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("The card CVV must be 3 or 4 digits"));
+    }
+    Ok(())
+}
+
+/// Returns the card brand, detected from the number prefix
+pub fn card_brand(number: &str) -> Option<&'static str> {
+    let prefix = |len: usize| number.get(..len).and_then(|p| p.parse::<u32>().ok());
+    match (prefix(1)?, prefix(2)?, prefix(4)?) {
+        (4, ..) => Some("Visa"),
+        (_, 51..=55, _) | (.., 2221..=2720) => Some("Mastercard"),
+        (_, 34 | 37, _) => Some("American Express"),
+        (_, 65, _) | (.., 6011) => Some("Discover"),
+        (_, 35, _) => Some("JCB"),
+        (_, 36 | 38 | 39, _) | (.., 3000..=3059) => Some("Diners Club"),
+        (_, 62, _) => Some("UnionPay"),
+        _ => None,
+    }
+}
+
+/// Returns the card number grouped by 4 digits, the American Express numbers
+/// are grouped by 4, 6 and 5
+fn group_card_number(number: &str) -> String {
+    let groups: &[usize] = if number.len() == 15 && card_brand(number) == Some("American Express") {
+        &[4, 6, 5]
+    } else {
+        &[4; 5]
+    };
+    let mut grouped = String::new();
+    let mut rest = number;
+    for &size in groups.iter().chain(std::iter::repeat(&4)) {
+        if rest.is_empty() {
+            break;
+        }
+        let (group, tail) = rest.split_at(size.min(rest.len()));
+        if !grouped.is_empty() {
+            grouped.push(' ');
+        }
+        grouped.push_str(group);
+        rest = tail;
+    }
+    grouped
+}
+
+/// Returns true if the email address have a local part and a domain with a
+/// dot
+pub(crate) fn is_valid_email(email: &str) -> bool {
+    email.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && !domain.contains('@')
+            && domain
+                .split_once('.')
+                .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty())
+    }) && !email.contains(char::is_whitespace)
+}
+
+/// Check the email address, see [`is_valid_email`]
+fn check_email(email: &str) -> LprsResult<()> {
+    if !is_valid_email(email) {
+        return Err(invalid(&format!("`{email}` is not a valid email address")));
+    }
+    Ok(())
+}
+
+/// Check the phone number, digits with an optional leading `+` and the
+/// separators spaces, dashes, dots and parentheses
+fn check_phone(phone: &str) -> LprsResult<()> {
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+    let valid = (3..=20).contains(&digits)
+        && phone
+            .trim_start_matches('+')
+            .chars()
+            .all(|c| c.is_ascii_digit() || " -.()".contains(c));
+    if !valid {
+        return Err(invalid(&format!("`{phone}` is not a valid phone number")));
+    }
+    Ok(())
+}
+
+/// Check the private key, a PEM encoded private key (e.g. the OpenSSH one)
+fn check_private_key(private_key: &str) -> LprsResult<()> {
+    let private_key = private_key.trim();
+    let begin = private_key
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("-----BEGIN "))
+        .and_then(|line| line.strip_suffix("PRIVATE KEY-----"));
+    let end = private_key
+        .lines()
+        .last()
+        .and_then(|line| line.strip_prefix("-----END "))
+        .and_then(|line| line.strip_suffix("PRIVATE KEY-----"));
+    match (begin, end) {
+        (Some(begin), Some(end)) if begin == end => Ok(()),
+        _ => {
+            Err(invalid(
+                "The SSH private key must be a PEM private key, e.g. `-----BEGIN OPENSSH PRIVATE \
+                 KEY-----`",
+            ))
+        }
+    }
+}
+
+/// Returns the public key blob of the OpenSSH public key, `ALGORITHM BASE64
+/// [COMMENT]`
+///
+/// ## Errors
+/// - If the public key is invalid, or its algorithm is not the blob one
+fn public_key_blob(public_key: &str) -> LprsResult<Vec<u8>> {
+    let error = || invalid("The SSH public key must be `ALGORITHM BASE64 [COMMENT]`");
+    let mut parts = public_key.split_whitespace();
+    let (Some(algorithm), Some(blob)) = (parts.next(), parts.next()) else {
+        return Err(error());
+    };
+    let blob = crate::BASE64.decode(blob).map_err(|_| error())?;
+    // The blob starts with the algorithm name, prefixed with its length
+    let name_len = blob
+        .get(..4)
+        .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        .ok_or_else(error)?;
+    if blob.get(4..4 + name_len) != Some(algorithm.as_bytes()) {
+        return Err(error());
+    }
+    Ok(blob)
+}
+
+/// Parse the date, `YYYY-MM-DD`, returns the year, month and day
+fn parse_date(date: &str) -> LprsResult<(i64, u32, u32)> {
+    let error = || {
+        invalid(&format!(
+            "`{date}` is not a valid date, must be `YYYY-MM-DD`"
+        ))
+    };
+    let mut parts = date.split('-');
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(error());
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return Err(error());
+    }
+    let (year, month, day): (i64, u32, u32) = (
+        year.parse().map_err(|_| error())?,
+        month.parse().map_err(|_| error())?,
+        day.parse().map_err(|_| error())?,
+    );
+    let is_leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap => 29,
+        2 => 28,
+        _ => return Err(error()),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(error());
+    }
+    Ok((year, month, day))
+}
+
+/// Returns the today date in UTC, the year, month and day
+fn today() -> (i64, u32, u32) {
+    // The civil from days algorithm, <https://howardhinnant.github.io/date_algorithms.html>
+    let days = i64::try_from(utils::unix_timestamp() / 86400).unwrap_or_default() + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn_valid_numbers() {
+        assert_eq!(
+            check_card_number("4111 1111 1111 1111").unwrap(),
+            "4111111111111111"
+        );
+        assert_eq!(
+            check_card_number("3782-822463-10005").unwrap(),
+            "378282246310005"
+        );
+        assert_eq!(
+            check_card_number("5555555555554444").unwrap(),
+            "5555555555554444"
+        );
+    }
+
+    #[test]
+    fn luhn_invalid_numbers() {
+        // Wrong check digit
+        assert!(check_card_number("4111111111111112").is_err());
+        // Swapped digits
+        assert!(check_card_number("4111111111111121").is_err());
+        // Too short, too long and not digits
+        assert!(check_card_number("42").is_err());
+        assert!(check_card_number("41111111111111111111").is_err());
+        assert!(check_card_number("4111a11111111111").is_err());
+    }
+
+    #[test]
+    fn leap_days() {
+        assert_eq!(parse_date("2024-02-29").unwrap(), (2024, 2, 29));
+        assert_eq!(parse_date("2000-02-29").unwrap(), (2000, 2, 29));
+        // Not leap years, the century years must be divisible by 400
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("1900-02-29").is_err());
+        assert!(parse_date("2024-02-30").is_err());
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert!(parse_date("2024-04-31").is_err());
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("2024-00-10").is_err());
+        assert!(parse_date("2024-01-00").is_err());
+        assert!(parse_date("2024-1-01").is_err());
+        assert!(parse_date("2024-01-01-01").is_err());
+        assert!(parse_date("24-01-01").is_err());
+    }
+
+    #[test]
+    fn today_is_valid_date() {
+        let (year, month, day) = today();
+        assert!(parse_date(&format!("{year:04}-{month:02}-{day:02}")).is_ok());
+    }
+
+    #[test]
+    fn card_expiry() {
+        assert_eq!(parse_card_expiry("1/27").unwrap(), "01/2027");
+        assert_eq!(parse_card_expiry(" 12/2030 ").unwrap(), "12/2030");
+        assert!(parse_card_expiry("13/27").is_err());
+        assert!(parse_card_expiry("00/27").is_err());
+        assert!(parse_card_expiry("12/027").is_err());
+        assert!(parse_card_expiry("1227").is_err());
+
+        let card = |expiry: &str| {
+            Card {
+                expiry: Some(expiry.to_owned()),
+                ..Card::default()
+            }
+        };
+        let (year, month, _) = today();
+        // The card is valid until the end of its expiry month
+        assert!(!card(&format!("{month:02}/{year}")).is_expired());
+        assert!(card(&format!("{month:02}/{}", year - 1)).is_expired());
+        assert!(!card("12/9999").is_expired());
+        assert!(!Card::default().is_expired());
+    }
+
+    #[test]
+    fn api_token_expiry() {
+        let token = |expiry: &str| {
+            ApiToken {
+                token:  "token".to_owned(),
+                expiry: Some(expiry.to_owned()),
+            }
+        };
+        let (year, month, day) = today();
+        // The token is valid on its expiry day
+        assert!(!token(&format!("{year:04}-{month:02}-{day:02}")).is_expired());
+        assert!(token("2000-02-29").is_expired());
+        assert!(!token("9996-02-29").is_expired());
+    }
+}
@@ -0,0 +1,324 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use keepass::{
+    Database,
+    DatabaseKey,
+    config::DatabaseConfig,
+    db::{Entry, Group, Node, Value},
+};
+use secstr::SecStr;
+
+use super::{Vault, cipher::TotpHash, custom_field::FieldKind, otpauth::OtpAuth};
+use crate::{LprsError, LprsResult};
+
+/// The KeePass entry title field
+const TITLE_FIELD: &str = "Title";
+/// The KeePass entry username field
+const USERNAME_FIELD: &str = "UserName";
+/// The KeePass entry password field
+const PASSWORD_FIELD: &str = "Password";
+/// The KeePass entry URL field
+const URL_FIELD: &str = "URL";
+/// The KeePass entry notes field
+const NOTES_FIELD: &str = "Notes";
+/// The KeePassXC entry TOTP field, an `otpauth://` URI
+const OTP_FIELD: &str = "otp";
+/// The KeePass entry fields that are mapped to the vault fields
+const STANDARD_FIELDS: &[&str] = &[
+    TITLE_FIELD,
+    USERNAME_FIELD,
+    PASSWORD_FIELD,
+    URL_FIELD,
+    NOTES_FIELD,
+    OTP_FIELD,
+];
+
+/// The prefix of the custom fields that are named after a standard field, so
+/// they don't overwrite it. The custom fields that already start with it are
+/// prefixed too, so the prefix is always removed once on import
+const RENAMED_FIELD_PREFIX: &str = "lprs:";
+
+/// The separator of the nested groups in the vault folder
+const FOLDER_SEPARATOR: char = '/';
+
+/// Create the KeePass database key from the password and the key file
+///
+/// ## Errors
+/// - If can't read the key file
+/// - If there is no password nor key file
+fn database_key(password: Option<&str>, key_file: Option<&Path>) -> LprsResult<DatabaseKey> {
+    let mut key = DatabaseKey::new();
+    if let Some(password) = password {
+        key = key.with_password(password);
+    }
+    if let Some(key_file) = key_file {
+        key = key.with_keyfile(&mut fs::File::open(key_file)?)?;
+    }
+    if password.is_none() && key_file.is_none() {
+        return Err(LprsError::KeePass(
+            "The KDBX database needs a password or a key file".to_owned(),
+        ));
+    }
+    Ok(key)
+}
+
+/// Returns true if the custom field needs the [`RENAMED_FIELD_PREFIX`] in the
+/// KeePass entry
+fn needs_prefix(key: &str) -> bool {
+    STANDARD_FIELDS.contains(&key) || key.starts_with(RENAMED_FIELD_PREFIX)
+}
+
+/// Returns the custom field name of the KeePass entry field, without the
+/// [`RENAMED_FIELD_PREFIX`] if it's added by the export
+fn custom_field_name(key: String) -> String {
+    match key.strip_prefix(RENAMED_FIELD_PREFIX) {
+        Some(name) if needs_prefix(name) => name.to_owned(),
+        _ => key,
+    }
+}
+
+/// Returns the string value of the KeePass field, `None` if it's empty or
+/// binary
+fn value_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Unprotected(value) => Some(value.clone()),
+        Value::Protected(value) => Some(String::from_utf8_lossy(value.unsecure()).into_owned()),
+        Value::Bytes(_) => None,
+    }
+    .filter(|v| !v.is_empty())
+}
+
+/// Convert the KeePass entry to a vault
+///
+/// ## Errors
+/// - If the entry have unsupported `otpauth://` URI
+fn entry_to_vault(entry: &Entry, folder: Option<&str>) -> LprsResult<Vault> {
+    let mut fields: BTreeMap<String, String> = entry
+        .fields
+        .iter()
+        .filter_map(|(key, value)| value_string(value).map(|value| (key.clone(), value)))
+        .collect();
+    // The unprotected custom fields are not secrets
+    let text_fields: Vec<(String, FieldKind)> = entry
+        .fields
+        .iter()
+        .filter(|(key, value)| {
+            matches!(value, Value::Unprotected(_))
+                && fields.contains_key(*key)
+                && !STANDARD_FIELDS.contains(&key.as_str())
+        })
+        .map(|(key, _)| {
+            let kind = if fields[key].contains('\n') {
+                FieldKind::Multiline
+            } else {
+                FieldKind::Text
+            };
+            (custom_field_name(key.clone()), kind)
+        })
+        .collect();
+
+    let name = fields
+        .remove(TITLE_FIELD)
+        .unwrap_or_else(|| entry.uuid.to_string());
+    let (totp_secret, totp_hash, totp_digits) = match fields.remove(OTP_FIELD) {
+        Some(otp) => {
+            let otp_auth = otp.parse::<OtpAuth>().map_err(|err| {
+                LprsError::KeePass(format!("The `{name}` entry have invalid TOTP: {err}"))
+            })?;
+            otp_auth.check_supported()?;
+            (Some(otp_auth.secret), otp_auth.hash, otp_auth.digits)
+        }
+        None => (None, TotpHash::default(), super::otpauth::DEFAULT_DIGITS),
+    };
+
+    let mut vault = Vault {
+        folder: folder.map(str::to_owned),
+        ..Vault::new(
+            name,
+            fields.remove(USERNAME_FIELD),
+            fields.remove(PASSWORD_FIELD),
+            fields.remove(URL_FIELD),
+            fields.remove(NOTES_FIELD),
+            fields
+                .into_iter()
+                .map(|(key, value)| (custom_field_name(key), value))
+                .collect(),
+            totp_secret,
+            totp_hash,
+            totp_digits,
+        )
+    };
+    for (key, kind) in text_fields {
+        vault.set_field_kind(&key, kind);
+    }
+    Ok(vault)
+}
+
+/// Collect the vaults of the group and its sub groups, the groups names
+/// will be the vault folder, separated by `/`
+fn collect_vaults(
+    database: &Database,
+    group: &Group,
+    folder: Option<&str>,
+    vaults: &mut Vec<Vault>,
+) -> LprsResult<()> {
+    for node in &group.children {
+        match node {
+            Node::Entry(entry) => vaults.push(entry_to_vault(entry, folder)?),
+            Node::Group(child) => {
+                if database.meta.recyclebin_uuid == Some(child.uuid) {
+                    log::info!("Skipping the KeePass recycle bin group");
+                    continue;
+                }
+                let child_folder = match folder {
+                    Some(folder) => format!("{folder}{FOLDER_SEPARATOR}{}", child.name),
+                    None => child.name.clone(),
+                };
+                collect_vaults(database, child, Some(&child_folder), vaults)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the group of the given path, creates the missing groups
+fn group_by_path<'g>(group: &'g mut Group, path: &[&str]) -> &'g mut Group {
+    let Some((name, path)) = path.split_first() else {
+        return group;
+    };
+    let idx = group
+        .children
+        .iter()
+        .position(|node| matches!(node, Node::Group(g) if g.name == *name))
+        .unwrap_or_else(|| {
+            group.add_child(Group::new(name));
+            group.children.len() - 1
+        });
+    match &mut group.children[idx] {
+        Node::Group(child) => group_by_path(child, path),
+        Node::Entry(_) => unreachable!("The index is of a group"),
+    }
+}
+
+/// Convert the vault to a KeePass entry, the secrets and the secret custom
+/// fields are protected fields. The custom fields named after a standard field
+/// are prefixed with [`RENAMED_FIELD_PREFIX`]
+fn vault_to_entry(vault: &Vault) -> Entry {
+    let unprotected = |value: &str| Value::Unprotected(value.to_owned());
+    let protected = |value: &str| Value::Protected(SecStr::from(value));
+
+    let mut entry = Entry::new();
+    let fields = [
+        (TITLE_FIELD, Some(unprotected(&vault.name))),
+        (USERNAME_FIELD, vault.username.as_deref().map(unprotected)),
+        (PASSWORD_FIELD, vault.password.as_deref().map(protected)),
+        (URL_FIELD, vault.service.as_deref().map(unprotected)),
+        (NOTES_FIELD, vault.note.as_deref().map(unprotected)),
+        (
+            OTP_FIELD,
+            OtpAuth::from_vault(vault).map(|otp_auth| protected(&otp_auth.to_uri())),
+        ),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            entry.fields.insert(key.to_owned(), value);
+        }
+    }
+    for (key, value) in &vault.custom_fields {
+        let value = if vault.field_kind(key).is_secret() {
+            protected(value)
+        } else {
+            unprotected(value)
+        };
+        let key = if needs_prefix(key) {
+            format!("{RENAMED_FIELD_PREFIX}{key}")
+        } else {
+            key.clone()
+        };
+        entry.fields.insert(key, value);
+    }
+    entry
+}
+
+/// Open the KeePass KDBX database and returns its vaults.
+///
+/// The groups are the vaults folders, the nested groups are separated by
+/// `/`, and the recycle bin is skipped.
+///
+/// ## Errors
+/// - If there is no password nor key file, or can't read the key file
+/// - If the database can't be opened (wrong key or corrupted database)
+/// - If an entry have unsupported `otpauth://` URI
+pub fn import(
+    kdbx_data: &[u8],
+    password: Option<&str>,
+    key_file: Option<&Path>,
+) -> LprsResult<Vec<Vault>> {
+    let database = Database::parse(kdbx_data, database_key(password, key_file)?)
+        .map_err(|err| LprsError::KeePass(err.to_string()))?;
+
+    let mut vaults = Vec::new();
+    collect_vaults(&database, &database.root, None, &mut vaults)?;
+    Ok(vaults)
+}
+
+/// Returns the number of the vaults custom fields that are renamed in the
+/// KeePass entries, because they are named after a standard field
+pub fn renamed_fields(vaults: &[Vault]) -> usize {
+    vaults
+        .iter()
+        .flat_map(|vault| vault.custom_fields.keys())
+        .filter(|key| STANDARD_FIELDS.contains(&key.as_str()))
+        .count()
+}
+
+/// Export the vaults as KeePass KDBX 4 database, protected by the given
+/// password and key file. The typed items fields are exported as custom
+/// fields, and the custom fields named after a standard field are renamed.
+///
+/// ## Errors
+/// - If there is no password nor key file, or can't read the key file
+/// - If the database can't be saved
+pub fn export(
+    vaults: &[Vault],
+    password: Option<&str>,
+    key_file: Option<&Path>,
+) -> LprsResult<Vec<u8>> {
+    let key = database_key(password, key_file)?;
+    let mut database = Database::new(DatabaseConfig::default());
+    for vault in vaults {
+        let path: Vec<&str> = vault
+            .folder
+            .as_deref()
+            .map(|folder| {
+                folder
+                    .split(FOLDER_SEPARATOR)
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        group_by_path(&mut database.root, &path).add_child(vault_to_entry(&vault.flatten_item()));
+    }
+
+    let mut kdbx_data = Vec::new();
+    database
+        .save(&mut kdbx_data, key)
+        .map_err(|err| LprsError::KeePass(err.to_string()))?;
+    Ok(kdbx_data)
+}
@@ -17,40 +17,203 @@
 use std::{collections::BTreeMap, fmt, fs, path::PathBuf};
 
 use base64::Engine;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
-use crate::{LprsError, LprsResult};
+use self::{
+    attachment::{Attachment, HumanSize},
+    custom_field::FieldKind,
+    item::{Item, ItemType},
+};
+use crate::{LprsError, LprsResult, generator::policy::PasswordPolicy, utils};
 
+/// The attachment module, the files stored inside the vaults
+pub mod attachment;
+/// The audit module, used to find the weak, reused and old passwords
+pub mod audit;
+/// The backup module, used to export the vaults as a single encrypted file
+pub mod backup;
+/// The Bitwarden module, used to import and export the Bitwarden JSON format
+pub mod bitwarden;
+/// The breach module, used to look up the passwords in a breached passwords
+/// file
+pub mod breach;
 /// The chiper module, used to encrypt and decrypt the vaults
 pub mod cipher;
+/// The CSV module, used to import and export the vaults as CSV
+pub mod csv;
+/// The custom field module, the kinds of the vault custom fields
+pub mod custom_field;
+/// The diff module, used to compare two vaults lists
+pub mod diff;
+/// The Google Authenticator module, used to decode its migration payloads
+pub mod google_auth;
+/// The item module, the typed vault items and their fields
+pub mod item;
+/// The KeePass module, used to import and export the KDBX databases
+pub mod kdbx;
+/// The otpauth module, used to build and parse the `otpauth://` URIs
+pub mod otpauth;
+/// The plaintext module, used to import and export the plaintext JSON
+pub mod plaintext;
+/// The QR module, used to render and decode the QR codes
+pub mod qr;
+/// The strength module, used to score the passwords and enforce their minimum
+/// strength
+pub mod strength;
+
+/// The header of the decrypted vaults file, followed by the vaults as JSON.
+///
+/// A vaults file without it is the legacy bincode vaults file, which will be
+/// rewritten in the new format on the next change.
+const VAULTS_FILE_HEADER: &[u8] = b"LPRS\0JSON";
+
+/// The masked value of the secrets
+pub(crate) const MASK: &str = "********";
+
+/// The vaults import and export formats
+#[derive(Default, Clone, Debug, ValueEnum, Eq, PartialEq)]
+pub enum Format {
+    /// The lprs format, a single authenticated encrypted file, encrypted with
+    /// the master password or the given password
+    #[default]
+    Lprs,
+    /// The legacy lprs JSON format, the vaults fields are encrypted separately
+    /// with the master password or the given password
+    LprsJson,
+    /// The Bitwarden unencrypted JSON format
+    #[value(name = "bitwarden")]
+    BitWarden,
+    /// The KeePass KDBX 4 database, protected by its password and key file
+    Kdbx,
+    /// The unencrypted CSV format, with columns mapping and presets
+    Csv,
+    /// The Google Authenticator export, the `otpauth-migration://` URIs or
+    /// their QR code PNG images (import only)
+    GoogleAuthMigration,
+}
+
+impl Format {
+    /// Returns the file extension of the format, `None` if the format don't
+    /// have a specific one
+    pub const fn extension(&self) -> Option<&'static str> {
+        match self {
+            Self::LprsJson | Self::BitWarden => Some("json"),
+            Self::Kdbx => Some("kdbx"),
+            Self::Csv => Some("csv"),
+            Self::Lprs | Self::GoogleAuthMigration => None,
+        }
+    }
+
+    /// Returns true if the vaults can be exported in this format
+    pub const fn is_exportable(&self) -> bool {
+        !matches!(self, Self::GoogleAuthMigration)
+    }
+
+    /// Returns true if the format is encrypted with a password
+    pub const fn is_encrypted(&self) -> bool {
+        matches!(self, Self::Lprs | Self::LprsJson | Self::Kdbx)
+    }
+}
 
 /// The vault struct
 #[derive(Clone, Debug, Deserialize, Serialize, Parser, Eq, PartialEq)]
 pub struct Vault {
     /// The name of the vault
-    pub name:          String,
+    #[arg(
+        required_unless_present = "interactive",
+        default_value_t,
+        hide_default_value = true
+    )]
+    pub name:               String,
     /// The username
     #[arg(short, long)]
-    pub username:      Option<String>,
+    pub username:           Option<String>,
     /// The password
     #[arg(skip)]
-    pub password:      Option<String>,
+    pub password:           Option<String>,
     /// The service name. e.g the website url
     #[arg(short, long)]
-    pub service:       Option<String>,
-    /// Add a note to the vault
+    pub service:            Option<String>,
+    /// Add a note to the vault, `-` to read it from the stdin
     #[arg(short, long)]
-    pub note:          Option<String>,
+    pub note:               Option<String>,
+    /// The folder of the vault
+    #[arg(long)]
+    #[serde(default)]
+    pub folder:             Option<String>,
     /// The vault custom fields
     #[arg(skip)]
-    pub custom_fields: BTreeMap<String, String>,
+    pub custom_fields:      BTreeMap<String, String>,
+    /// The kinds of the vault custom fields, the fields that are not here are
+    /// secrets
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_field_kinds: BTreeMap<String, FieldKind>,
     /// The TOTP secret
     #[arg(skip)]
-    pub totp_secret:   Option<String>,
+    pub totp_secret:        Option<String>,
     /// The TOTP hash function
     #[arg(long, value_name = "HASH_FUNCTION", value_enum, default_value_t)]
-    pub totp_hash:     cipher::TotpHash,
+    pub totp_hash:          cipher::TotpHash,
+    /// The TOTP code digits
+    #[arg(long, value_name = "DIGITS", default_value_t = otpauth::DEFAULT_DIGITS)]
+    #[arg(value_parser = clap::value_parser!(u8).range(6..=8))]
+    #[serde(default = "default_totp_digits")]
+    pub totp_digits:        u8,
+    /// The password policy of the vault, honoured when regenerating its
+    /// password
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy:             Option<PasswordPolicy>,
+    /// When the password was last changed, seconds since the Unix epoch.
+    /// `None` if it's unknown, e.g. the vaults added before tracking it
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_changed:   Option<u64>,
+    /// The files attached to the vault
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments:        Vec<Attachment>,
+    /// The vault item, its type and fields. A login by default
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Item::is_login")]
+    pub item:               Item,
+}
+
+/// The vault as it was stored in the legacy bincode vaults file
+#[derive(Deserialize)]
+struct LegacyVault {
+    name:          String,
+    username:      Option<String>,
+    password:      Option<String>,
+    service:       Option<String>,
+    note:          Option<String>,
+    custom_fields: BTreeMap<String, String>,
+    totp_secret:   Option<String>,
+    totp_hash:     cipher::TotpHash,
+}
+
+/// A vault field that failed to be decrypted while reloading the vaults from
+/// json data
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReloadIssue {
+    /// The vault index in the json data, starts from 1
+    pub vault_index: usize,
+    /// The vault name, `None` if the name itself failed to be decrypted
+    pub vault_name:  Option<String>,
+    /// The failed field, e.g. `password` or ``custom field `key` ``
+    pub field:       String,
+    /// Why the field failed to be decrypted
+    pub reason:      String,
+}
+
+/// Decrypt the fields of a single vault, collecting the failed fields
+struct FieldDecryptor<'a> {
+    key:         &'a [u8; 32],
+    vault_index: usize,
+    vault_name:  Option<String>,
+    issues:      &'a mut Vec<ReloadIssue>,
 }
 
 /// The vaults manager
@@ -76,6 +239,7 @@ impl Vault {
         custom_fields: BTreeMap<String, String>,
         totp_secret: Option<impl Into<String>>,
         totp_hash: cipher::TotpHash,
+        totp_digits: u8,
     ) -> Self {
         Self {
             name: name.into(),
@@ -83,12 +247,122 @@ impl Vault {
             password: password.map(Into::into),
             service: service.map(Into::into),
             note: note.map(Into::into),
+            folder: None,
             custom_fields,
+            custom_field_kinds: BTreeMap::new(),
             totp_secret: totp_secret.map(Into::into),
             totp_hash,
+            totp_digits,
+            policy: None,
+            password_changed: None,
+            attachments: Vec::new(),
+            item: Item::Login,
+        }
+    }
+
+    /// Set the vault password, and its change time if it's changed
+    pub fn set_password(&mut self, password: Option<String>) {
+        if password != self.password {
+            self.password_changed = password.is_some().then(utils::unix_timestamp);
+            self.password = password;
         }
     }
 
+    /// Returns the kind of the custom field, a secret by default
+    pub fn field_kind(&self, key: &str) -> FieldKind {
+        self.custom_field_kinds
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Set the kind of the custom field, only the non-secret kinds are stored
+    pub fn set_field_kind(&mut self, key: &str, kind: FieldKind) {
+        if kind == FieldKind::Secret {
+            self.custom_field_kinds.remove(key);
+        } else {
+            self.custom_field_kinds.insert(key.to_owned(), kind);
+        }
+    }
+
+    /// Returns the values of the custom fields that are not secrets, they are
+    /// searched with the other vault fields
+    pub fn public_custom_fields(&self) -> impl Iterator<Item = &str> {
+        self.custom_fields
+            .iter()
+            .filter(|(key, _)| !self.field_kind(key).is_secret())
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Check the custom fields values against their kinds
+    ///
+    /// ## Errors
+    /// - If there is a kind of a field that doesn't exist
+    /// - If a field value is invalid for its kind
+    pub fn check_custom_fields(&self) -> LprsResult<()> {
+        for (key, kind) in &self.custom_field_kinds {
+            let Some(value) = self.custom_fields.get(key) else {
+                return Err(LprsError::InvalidCustomField(format!(
+                    "The `{}` vault have a kind for the `{key}` field, but there is no such field",
+                    self.name
+                )));
+            };
+            kind.check(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the vault display, the password, the TOTP secret, the secret
+    /// custom fields and the item secrets are masked unless `show_secrets` is
+    /// true
+    pub const fn display(&self, show_secrets: bool) -> VaultDisplay<'_> {
+        VaultDisplay {
+            vault: self,
+            show_secrets,
+        }
+    }
+
+    /// Validate the vault, used for the vaults that are not created by the
+    /// commands, e.g. the imported vaults
+    ///
+    /// ## Errors
+    /// - If a custom field starts with the reserved prefix
+    /// - If an attachment is invalid or the attachments are too large
+    /// - If the typed item is invalid
+    /// - If a custom field value is invalid for its kind
+    pub fn validate(&self) -> LprsResult<()> {
+        if self
+            .custom_fields
+            .keys()
+            .any(|k| k.starts_with(crate::RESERVED_FIELD_PREFIX))
+        {
+            return Err(LprsError::ReservedPrefix(crate::RESERVED_FIELD_PREFIX));
+        }
+        attachment::check_vault(self)?;
+        self.item.validate()?;
+        self.check_custom_fields()
+    }
+
+    /// Returns the current codes of the `totp` custom fields, with the field
+    /// key and the remaining seconds. The codes are of 6 digits and SHA1
+    ///
+    /// ## Errors
+    /// - If a field value is invalid base32
+    pub fn custom_totp_codes(&self) -> LprsResult<Vec<(&str, String, u8)>> {
+        self.custom_fields
+            .iter()
+            .filter(|(key, _)| self.field_kind(key) == FieldKind::Totp)
+            .map(|(key, secret)| {
+                let (code, remaining) = cipher::totp_now(
+                    secret,
+                    &cipher::TotpHash::default(),
+                    otpauth::DEFAULT_DIGITS,
+                )?;
+                Ok((key.as_str(), code, remaining))
+            })
+            .collect()
+    }
+
     /// Returns true if the vault is empty
     pub fn is_empty(&self) -> bool {
         self.username.is_none()
@@ -96,6 +370,31 @@ impl Vault {
             && self.service.is_none()
             && self.note.is_none()
             && self.custom_fields.is_empty()
+            && matches!(self.item, Item::Login | Item::Note)
+    }
+
+    /// Returns the vault with its item fields as custom fields, for the formats
+    /// that don't support the typed items. The existing custom fields are kept
+    pub fn flatten_item(&self) -> Self {
+        let mut vault = self.clone();
+        for (_, label, value, secret) in self.item.fields() {
+            if vault.custom_fields.contains_key(label) {
+                continue;
+            }
+            vault
+                .custom_fields
+                .insert(label.to_owned(), value.to_owned());
+            if !secret {
+                let kind = if value.contains('\n') {
+                    FieldKind::Multiline
+                } else {
+                    FieldKind::Text
+                };
+                vault.set_field_kind(label, kind);
+            }
+        }
+        vault.item = Item::Login;
+        vault
     }
 
     /// Return the name of the vault with the service if there
@@ -108,8 +407,16 @@ impl Vault {
         if let Some(ref service) = self.service {
             write!(&mut list_name, " ({service})").expect("String never fail");
         }
-        if self.username.is_none() && self.password.is_none() && self.note.is_some() {
-            write!(&mut list_name, " *Note").expect("String never fail");
+        match self.item.item_type() {
+            ItemType::Login => {
+                if self.username.is_none() && self.password.is_none() && self.note.is_some() {
+                    write!(&mut list_name, " *Note").expect("String never fail");
+                }
+            }
+            ItemType::Note => write!(&mut list_name, " *Note").expect("String never fail"),
+            item_type => {
+                write!(&mut list_name, " *{}", item_type.label()).expect("String never fail");
+            }
         }
         list_name
     }
@@ -148,83 +455,195 @@ impl Vaults {
                 .vaults
                 .iter()
                 .map(|v| {
-                    LprsResult::Ok(Vault::new(
-                        encrypt(&v.name),
-                        v.username.as_ref().map(|u| encrypt(u)),
-                        v.password.as_ref().map(|p| encrypt(p)),
-                        v.service.as_ref().map(|s| encrypt(s)),
-                        v.note.as_ref().map(|n| encrypt(n)),
-                        v.custom_fields
+                    LprsResult::Ok(Vault {
+                        folder: v.folder.as_ref().map(|f| encrypt(f)),
+                        policy: v.policy.clone(),
+                        password_changed: v.password_changed,
+                        attachments: v
+                            .attachments
                             .iter()
-                            .map(|(key, value)| (encrypt(key), encrypt(value)))
+                            .map(|a| {
+                                Attachment {
+                                    name: encrypt(&a.name),
+                                    mime: encrypt(&a.mime),
+                                    data: cipher::encrypt(encryption_key, &a.data),
+                                }
+                            })
                             .collect(),
-                        v.totp_secret.as_ref().map(|t| encrypt(t)),
-                        v.totp_hash.clone(),
-                    ))
+                        item: v
+                            .item
+                            .map_fields(|_, value| Some(encrypt(value)))
+                            .unwrap_or_default(),
+                        custom_field_kinds: v
+                            .custom_field_kinds
+                            .iter()
+                            .map(|(key, kind)| (encrypt(key), *kind))
+                            .collect(),
+                        ..Vault::new(
+                            encrypt(&v.name),
+                            v.username.as_ref().map(|u| encrypt(u)),
+                            v.password.as_ref().map(|p| encrypt(p)),
+                            v.service.as_ref().map(|s| encrypt(s)),
+                            v.note.as_ref().map(|n| encrypt(n)),
+                            v.custom_fields
+                                .iter()
+                                .map(|(key, value)| (encrypt(key), encrypt(value)))
+                                .collect(),
+                            v.totp_secret.as_ref().map(|t| encrypt(t)),
+                            v.totp_hash.clone(),
+                            v.totp_digits,
+                        )
+                    })
                 })
                 .collect::<LprsResult<Vec<_>>>()?,
         )
         .map_err(Into::into)
     }
 
-    /// Reload the vaults from json data.
+    /// Reload the vaults from json data, returns the vaults with the fields
+    /// that failed to be decrypted.
+    ///
+    /// In the strict mode (`lenient` is false) any field that can't be
+    /// decrypted is an error that reports all the failed fields. In the lenient
+    /// mode the failed fields are dropped and returned, but a vault that can't
+    /// be imported (e.g. its name or a required field of its item failed) is
+    /// still an error, the vaults are never returned partially.
     ///
     /// ## Errors
-    /// - If base64 decoding failed (of the vault field encrypted data)
-    /// - If decryption failed (wrong master password or the data is corrupted)
+    /// - If the json data is invalid
+    /// - If a field failed to be decrypted, in the strict mode (wrong master
+    ///   password or the data is corrupted)
+    /// - If a vault can't be imported, even in the lenient mode
+    /// - If a vault have a custom field with the reserved prefix
+    /// - If a custom field value is invalid for its kind
     ///
     /// This function used to import backup vaults.
-    pub fn json_reload(master_password: &[u8; 32], json_data: &[u8]) -> LprsResult<Vec<Vault>> {
-        let decrypt = |val: &str| {
-            String::from_utf8(cipher::decrypt(
-                master_password,
-                &crate::BASE64.decode(val)?,
-            )?)
-            .map_err(|err| LprsError::Other(err.to_string()))
-        };
+    pub fn json_reload(
+        master_password: &[u8; 32],
+        json_data: &[u8],
+        lenient: bool,
+    ) -> LprsResult<(Vec<Vault>, Vec<ReloadIssue>)> {
+        let mut issues = Vec::new();
+        let mut vaults = Vec::new();
+        // A wrong password fails all the names, so the vaults can't be dropped
+        let mut has_dropped = false;
 
-        let vaults = serde_json::from_slice::<Vec<Vault>>(json_data)?
+        for (idx, v) in serde_json::from_slice::<Vec<Vault>>(json_data)?
             .into_iter()
-            .map(|v| {
-                LprsResult::Ok(Vault::new(
-                    decrypt(&v.name)?,
-                    v.username.as_ref().and_then(|u| decrypt(u).ok()),
-                    v.password.as_ref().and_then(|p| decrypt(p).ok()),
-                    v.service.as_ref().and_then(|s| decrypt(s).ok()),
-                    v.note.as_ref().and_then(|n| decrypt(n).ok()),
-                    v.custom_fields
-                        .into_iter()
-                        .map(|(key, value)| LprsResult::Ok((decrypt(&key)?, decrypt(&value)?)))
-                        .collect::<LprsResult<_>>()?,
-                    v.totp_secret.as_ref().and_then(|t| decrypt(t).ok()),
+            .enumerate()
+        {
+            let mut decryptor = FieldDecryptor {
+                key:         master_password,
+                vault_index: idx + 1,
+                vault_name:  None,
+                issues:      &mut issues,
+            };
+            let Some(name) = decryptor.decrypt("name", &v.name) else {
+                has_dropped = true;
+                continue;
+            };
+            decryptor.vault_name = Some(name.clone());
+
+            let mut custom_fields = BTreeMap::new();
+            for (field_idx, (key, value)) in v.custom_fields.iter().enumerate() {
+                let Some(key) =
+                    decryptor.decrypt(&format!("custom field #{} name", field_idx + 1), key)
+                else {
+                    continue;
+                };
+                if let Some(value) = decryptor.decrypt(&format!("custom field `{key}`"), value) {
+                    custom_fields.insert(key, value);
+                }
+            }
+
+            // The kinds of the fields that failed to be decrypted are dropped
+            let mut custom_field_kinds = BTreeMap::new();
+            for (field_idx, (key, kind)) in v.custom_field_kinds.iter().enumerate() {
+                if let Some(key) =
+                    decryptor.decrypt(&format!("custom field kind #{} name", field_idx + 1), key)
+                {
+                    if custom_fields.contains_key(&key) {
+                        custom_field_kinds.insert(key, *kind);
+                    }
+                }
+            }
+
+            let mut attachments = Vec::new();
+            for (attachment_idx, attachment) in v.attachments.iter().enumerate() {
+                let Some(name) = decryptor.decrypt(
+                    &format!("attachment #{} name", attachment_idx + 1),
+                    &attachment.name,
+                ) else {
+                    continue;
+                };
+                let field = format!("attachment `{name}`");
+                if let (Some(mime), Some(data)) = (
+                    decryptor.decrypt(&field, &attachment.mime),
+                    decryptor.decrypt_bytes(&field, &attachment.data),
+                ) {
+                    attachments.push(Attachment { name, mime, data });
+                }
+            }
+
+            // A typed item that its required field failed to be decrypted can't be imported
+            let Some(item) = v
+                .item
+                .map_fields(|field, value| decryptor.decrypt(field, value))
+            else {
+                has_dropped = true;
+                continue;
+            };
+
+            vaults.push(Vault {
+                folder: decryptor.decrypt_opt("folder", &v.folder),
+                policy: v.policy.clone(),
+                password_changed: v.password_changed,
+                attachments,
+                item,
+                custom_field_kinds,
+                ..Vault::new(
+                    name,
+                    decryptor.decrypt_opt("username", &v.username),
+                    decryptor.decrypt_opt("password", &v.password),
+                    decryptor.decrypt_opt("service", &v.service),
+                    decryptor.decrypt_opt("note", &v.note),
+                    custom_fields,
+                    decryptor.decrypt_opt("TOTP secret", &v.totp_secret),
                     v.totp_hash,
-                ))
-            })
-            .collect::<LprsResult<Vec<_>>>()?;
+                    v.totp_digits,
+                )
+            });
+        }
 
-        if vaults.iter().any(|v| {
-            v.custom_fields
-                .iter()
-                .any(|(k, _)| k.starts_with(crate::RESERVED_FIELD_PREFIX))
-        }) {
-            return Err(LprsError::ReservedPrefix(crate::RESERVED_FIELD_PREFIX));
+        if (!lenient || has_dropped) && !issues.is_empty() {
+            return Err(LprsError::FieldDecryption(
+                issues
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ));
         }
 
-        Ok(vaults)
+        vaults.iter().try_for_each(Vault::validate)?;
+
+        Ok((vaults, issues))
     }
 
     /// Encrypt the vaults then export it to the file
     ///
     /// ## Errors
     /// - Writing to the file failed
-    pub fn try_export(self) -> LprsResult<()> {
+    pub fn try_export(&self) -> LprsResult<()> {
         log::debug!(
             "Trying to export the vaults to the file: {}",
             self.vaults_file.display()
         );
+        let mut vaults_data = VAULTS_FILE_HEADER.to_vec();
+        serde_json::to_writer(&mut vaults_data, &self.vaults)?;
         fs::write(
             &self.vaults_file,
-            cipher::encrypt(&self.master_password, &bincode::serialize(&self.vaults)?),
+            cipher::encrypt(&self.master_password, &vaults_data),
         )
         .map_err(LprsError::Io)
     }
@@ -234,44 +653,177 @@ impl Vaults {
     /// ## Errors
     /// - Reading the file failed
     /// - Decryption failed (wrong master password or the data is corrupted)
-    /// - Bytecode deserialization failed (the data is corrupted)
+    /// - Deserialization failed (the data is corrupted)
     pub fn try_reload(vaults_file: PathBuf, master_password: [u8; 32]) -> LprsResult<Self> {
         let vaults_data = fs::read(&vaults_file)?;
 
         let vaults: Vec<Vault> = if vaults_data.is_empty() {
             vec![]
         } else {
-            bincode::deserialize(&cipher::decrypt(&master_password, &vaults_data)?)?
+            let vaults_data = cipher::decrypt(&master_password, &vaults_data)?;
+            if let Some(json_data) = vaults_data.strip_prefix(VAULTS_FILE_HEADER) {
+                serde_json::from_slice(json_data)?
+            } else {
+                log::info!("Reloading a legacy bincode vaults file");
+                bincode::deserialize::<Vec<LegacyVault>>(&vaults_data)?
+                    .into_iter()
+                    .map(Vault::from)
+                    .collect()
+            }
         };
 
         Ok(Self::new(master_password, vaults_file, vaults))
     }
 }
 
+impl FieldDecryptor<'_> {
+    /// Decrypt the base64 field, returns `None` and records the issue if it
+    /// failed
+    fn decrypt(&mut self, field: &str, value: &str) -> Option<String> {
+        let decrypted = crate::BASE64
+            .decode(value)
+            .map_err(LprsError::from)
+            .and_then(|data| cipher::decrypt(self.key, &data))
+            .and_then(|data| String::from_utf8(data).map_err(LprsError::from));
+        self.check(field, decrypted)
+    }
+
+    /// Decrypt the binary field, see [`FieldDecryptor::decrypt`]
+    fn decrypt_bytes(&mut self, field: &str, value: &[u8]) -> Option<Vec<u8>> {
+        let decrypted = cipher::decrypt(self.key, value);
+        self.check(field, decrypted)
+    }
+
+    /// Returns the decrypted value, or records the issue and returns `None`
+    fn check<T>(&mut self, field: &str, decrypted: LprsResult<T>) -> Option<T> {
+        match decrypted {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.issues.push(ReloadIssue {
+                    vault_index: self.vault_index,
+                    vault_name:  self.vault_name.clone(),
+                    field:       field.to_owned(),
+                    reason:      match err {
+                        LprsError::Decryption => "wrong key or corrupted data".to_owned(),
+                        err => err.to_string(),
+                    },
+                });
+                None
+            }
+        }
+    }
+
+    /// Decrypt the optional field, see [`FieldDecryptor::decrypt`]
+    fn decrypt_opt(&mut self, field: &str, value: &Option<String>) -> Option<String> {
+        value.as_ref().and_then(|value| self.decrypt(field, value))
+    }
+}
+
+impl fmt::Display for ReloadIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Vault #{}", self.vault_index)?;
+        if let Some(ref name) = self.vault_name {
+            write!(f, " (`{name}`)")?;
+        }
+        write!(f, ": can't decrypt its {}: {}", self.field, self.reason)
+    }
+}
+
+impl From<LegacyVault> for Vault {
+    fn from(vault: LegacyVault) -> Self {
+        Self::new(
+            vault.name,
+            vault.username,
+            vault.password,
+            vault.service,
+            vault.note,
+            vault.custom_fields,
+            vault.totp_secret,
+            vault.totp_hash,
+            otpauth::DEFAULT_DIGITS,
+        )
+    }
+}
+
+/// The default TOTP digits of the vaults that don't have it
+pub(crate) const fn default_totp_digits() -> u8 {
+    otpauth::DEFAULT_DIGITS
+}
+
+/// The vault display, with the secrets masked unless they are shown
+pub struct VaultDisplay<'a> {
+    /// The displayed vault
+    vault:        &'a Vault,
+    /// Show the secrets instead of masking them
+    show_secrets: bool,
+}
+
 impl fmt::Display for Vault {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Name: {}", self.name)?;
-        if let Some(ref username) = self.username {
+        self.display(false).fmt(f)
+    }
+}
+
+impl fmt::Display for VaultDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let vault = self.vault;
+        let secret = |value| if self.show_secrets { value } else { MASK };
+
+        write!(f, "Name: {}", vault.name)?;
+        if !vault.item.is_login() {
+            write!(f, "\nType: {}", vault.item.item_type().label())?;
+        }
+        if let Some(ref username) = vault.username {
             write!(f, "\nUsername: {username}")?;
         }
-        if let Some(ref password) = self.password {
-            write!(f, "\nPassword: {password}")?;
+        if let Some(ref password) = vault.password {
+            write!(f, "\nPassword: {}", secret(password))?;
         }
-        if let Some(ref service) = self.service {
+        if let Some(ref service) = vault.service {
             write!(f, "\nService: {service}")?;
         }
-        if let Some(ref note) = self.note {
+        if let Some(ref folder) = vault.folder {
+            write!(f, "\nFolder: {folder}")?;
+        }
+        for (label, value, is_secret) in vault.item.display_fields() {
+            if is_secret && !self.show_secrets {
+                write!(f, "\n{label}: {MASK}")?;
+            } else if value.contains('\n') {
+                write!(f, "\n{label}:\n{}", value.trim_end())?;
+            } else {
+                write!(f, "\n{label}: {value}")?;
+            }
+        }
+        if let Some(ref note) = vault.note {
             write!(f, "\nNote:\n{note}")?;
         }
-        if let Some(ref totp_secret) = self.totp_secret {
-            write!(f, "\nTOTP Secret: {totp_secret}")?;
+        if let Some(ref totp_secret) = vault.totp_secret {
+            write!(f, "\nTOTP Secret: {}", secret(totp_secret))?;
+        }
+        for (key, value) in &vault.custom_fields {
+            // The reserved fields are added only to be displayed, e.g. the TOTP codes
+            let (key, is_secret) = match key.strip_prefix(crate::RESERVED_FIELD_PREFIX) {
+                Some(key) => (key, false),
+                None => (key.as_str(), vault.field_kind(key).is_secret()),
+            };
+            if is_secret && !self.show_secrets {
+                write!(f, "\n{key}: {MASK}")?;
+            } else if value.contains('\n') {
+                write!(f, "\n{key}:\n{}", value.trim_end())?;
+            } else {
+                write!(f, "\n{key}: {value}")?;
+            }
+        }
+        if let Some(ref policy) = vault.policy {
+            write!(f, "\nPassword Policy: {policy}")?;
         }
-        for (key, value) in &self.custom_fields {
+        for attachment in &vault.attachments {
             write!(
                 f,
-                "\n{}: {value}",
-                key.strip_prefix(crate::RESERVED_FIELD_PREFIX)
-                    .unwrap_or(key)
+                "\nAttachment: {} ({}, {})",
+                attachment.name,
+                attachment.mime,
+                HumanSize(attachment.data.len())
             )?;
         }
 
@@ -0,0 +1,232 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::str::FromStr;
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+
+use super::{Vault, cipher::TotpHash};
+use crate::{LprsError, LprsResult};
+
+/// The characters that will not be percent-encoded in the URI components
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The default TOTP digits
+pub const DEFAULT_DIGITS: u8 = 6;
+/// The default TOTP period in seconds, the only one supported by the vaults
+pub const DEFAULT_PERIOD: u64 = 30;
+
+/// A parsed `otpauth://totp/` URI, the format used by the authenticator apps
+/// QR codes
+///
+/// See <https://github.com/google/google-authenticator/wiki/Key-Uri-Format>
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OtpAuth {
+    /// The issuer of the TOTP, e.g. the service name
+    pub issuer:  Option<String>,
+    /// The account name, e.g. the username
+    pub account: String,
+    /// The base32 TOTP secret
+    pub secret:  String,
+    /// The TOTP hash function
+    pub hash:    TotpHash,
+    /// The TOTP code digits
+    pub digits:  u8,
+    /// The TOTP period in seconds
+    pub period:  u64,
+}
+
+impl OtpAuth {
+    /// Create the [`OtpAuth`] of the given vault, returns `None` if the vault
+    /// don't have a TOTP secret.
+    ///
+    /// The vault name will be the issuer, and the username (or the vault name
+    /// if there is no username) will be the account name.
+    pub fn from_vault(vault: &Vault) -> Option<Self> {
+        vault.totp_secret.as_ref().map(|secret| {
+            Self {
+                issuer:  Some(vault.name.clone()),
+                account: vault.username.clone().unwrap_or_else(|| vault.name.clone()),
+                secret:  secret.clone(),
+                hash:    vault.totp_hash.clone(),
+                digits:  vault.totp_digits,
+                period:  DEFAULT_PERIOD,
+            }
+        })
+    }
+
+    /// Returns an error if the digits or the period are not supported by the
+    /// vaults
+    ///
+    /// ## Errors
+    /// - If the digits isn't between 6 and 8 or the period isn't
+    ///   [`DEFAULT_PERIOD`]
+    pub fn check_supported(&self) -> LprsResult<()> {
+        if !(6..=8).contains(&self.digits) || self.period != DEFAULT_PERIOD {
+            return Err(LprsError::InvalidOtpAuth(format!(
+                "Only 6 to 8 digits TOTP with {DEFAULT_PERIOD}s period is supported, found {} \
+                 digits with {}s period",
+                self.digits, self.period
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the `otpauth://totp/` URI
+    pub fn to_uri(&self) -> String {
+        let encode = |s: &str| utf8_percent_encode(s, URI_COMPONENT).to_string();
+
+        let label = match self.issuer {
+            Some(ref issuer) => format!("{}:{}", encode(issuer), encode(&self.account)),
+            None => encode(&self.account),
+        };
+        let mut uri = format!(
+            "otpauth://totp/{label}?secret={}&algorithm={}&digits={}&period={}",
+            self.secret.trim_end_matches('='),
+            match self.hash {
+                TotpHash::Sha1 => "SHA1",
+                TotpHash::Sha256 => "SHA256",
+                TotpHash::Sha512 => "SHA512",
+            },
+            self.digits,
+            self.period
+        );
+        if let Some(ref issuer) = self.issuer {
+            uri.push_str(&format!("&issuer={}", encode(issuer)));
+        }
+        uri
+    }
+}
+
+/// Parse the TOTP field of the other managers exports, it can be an
+/// `otpauth://` URI or a base32 secret (SHA1 with 6 digits). Returns the
+/// secret, the hash function and the digits, `None` if the field is empty.
+///
+/// ## Errors
+/// - If the URI is invalid or unsupported by the vaults
+/// - If the secret is invalid base32
+pub fn parse_totp_field(field: &str) -> LprsResult<Option<(String, TotpHash, u8)>> {
+    let field = field.trim();
+    if field.starts_with("otpauth://") {
+        let otp_auth = field.parse::<OtpAuth>()?;
+        otp_auth.check_supported()?;
+        return Ok(Some((otp_auth.secret, otp_auth.hash, otp_auth.digits)));
+    }
+    if field.is_empty() {
+        return Ok(None);
+    }
+
+    let secret = field.replace(' ', "").to_uppercase();
+    super::cipher::base32_decode(&secret).map_err(|_| {
+        LprsError::Base32("Invalid TOTP secret, must be valid base32 string".to_owned())
+    })?;
+    Ok(Some((secret, TotpHash::default(), DEFAULT_DIGITS)))
+}
+
+/// Returns the TOTP field of the vault for the other managers exports, the
+/// base32 secret if it's SHA1 with 6 digits, otherwise the `otpauth://` URI.
+pub fn totp_field(vault: &Vault) -> Option<String> {
+    OtpAuth::from_vault(vault).map(|otp_auth| {
+        if otp_auth.hash == TotpHash::Sha1 && otp_auth.digits == DEFAULT_DIGITS {
+            otp_auth.secret
+        } else {
+            otp_auth.to_uri()
+        }
+    })
+}
+
+impl FromStr for OtpAuth {
+    type Err = LprsError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| LprsError::InvalidOtpAuth(reason.to_owned());
+        let decode = |s: &str| {
+            percent_decode_str(s)
+                .decode_utf8()
+                .map(|s| s.into_owned())
+                .map_err(|_| invalid("The URI is not a valid UTF-8 after decoding it"))
+        };
+
+        let rest = uri
+            .trim()
+            .strip_prefix("otpauth://")
+            .ok_or_else(|| invalid("The URI must starts with `otpauth://`"))?;
+        let (otp_type, rest) = rest
+            .split_once('/')
+            .ok_or_else(|| invalid("The URI don't have a label"))?;
+        if !otp_type.eq_ignore_ascii_case("totp") {
+            return Err(invalid(&format!(
+                "Unsupported OTP type `{otp_type}`, only `totp` is supported"
+            )));
+        }
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = decode(label)?;
+        let (label_issuer, account) = match label.split_once(':') {
+            Some((issuer, account)) => (Some(issuer.trim().to_owned()), account.trim().to_owned()),
+            None => (None, label.trim().to_owned()),
+        };
+
+        let mut secret = None;
+        let mut issuer = None;
+        let mut hash = TotpHash::default();
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+        for (key, value) in query.split('&').filter_map(|kv| kv.split_once('=')) {
+            let value = decode(value)?;
+            match key.to_lowercase().as_str() {
+                "secret" => secret = Some(value.replace(' ', "").to_uppercase()),
+                "issuer" => issuer = Some(value),
+                "algorithm" => {
+                    hash = match value.to_uppercase().as_str() {
+                        "SHA1" => TotpHash::Sha1,
+                        "SHA256" => TotpHash::Sha256,
+                        "SHA512" => TotpHash::Sha512,
+                        _ => return Err(invalid(&format!("Unsupported algorithm `{value}`"))),
+                    }
+                }
+                "digits" => {
+                    digits = value
+                        .parse()
+                        .map_err(|_| invalid(&format!("Invalid digits `{value}`")))?
+                }
+                "period" => {
+                    period = value
+                        .parse()
+                        .map_err(|_| invalid(&format!("Invalid period `{value}`")))?
+                }
+                _ => log::debug!("Ignoring unknown otpauth parameter `{key}`"),
+            }
+        }
+
+        let secret = secret.ok_or_else(|| invalid("The URI don't have a `secret` parameter"))?;
+        super::cipher::base32_decode(&secret).map_err(|_| {
+            LprsError::Base32("Invalid TOTP secret, must be valid base32 string".to_owned())
+        })?;
+
+        Ok(Self {
+            issuer: issuer.or(label_issuer).filter(|i| !i.is_empty()),
+            account,
+            secret,
+            hash,
+            digits,
+            period,
+        })
+    }
+}
@@ -0,0 +1,269 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The plaintext JSON export, a documented and versioned JSON described by
+//! the JSON Schema in `docs/schemas/plaintext-export-v1.json`
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Vault, attachment::Attachment, cipher::TotpHash, custom_field::FieldKind, item::Item};
+use crate::{LprsError, LprsResult, generator::policy::PasswordPolicy};
+
+/// The `format` value of the plaintext export
+pub const PLAINTEXT_FORMAT: &str = "lprs-plaintext";
+/// The current version of the plaintext export
+pub const PLAINTEXT_VERSION: u32 = 1;
+/// The JSON Schema of the current version
+pub const PLAINTEXT_SCHEMA: &str = "https://lprs.4rs.nl/schemas/plaintext-export-v1.json";
+
+/// The plaintext JSON export
+#[derive(Deserialize, Serialize)]
+pub struct PlaintextExport {
+    /// The JSON Schema of the export
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema:  Option<String>,
+    /// Always [`PLAINTEXT_FORMAT`]
+    pub format:  String,
+    /// The export version, the versions newer than [`PLAINTEXT_VERSION`] are
+    /// not supported
+    pub version: u32,
+    /// The vaults
+    pub vaults:  Vec<PlaintextVault>,
+}
+
+/// A plaintext vault
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaintextVault {
+    /// The vault name
+    pub name:               String,
+    /// The vault username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username:           Option<String>,
+    /// The vault password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password:           Option<String>,
+    /// The vault service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service:            Option<String>,
+    /// The vault note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note:               Option<String>,
+    /// The vault folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder:             Option<String>,
+    /// The vault custom fields
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_fields:      BTreeMap<String, String>,
+    /// The kinds of the custom fields, the fields without a kind are secrets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_field_kinds: BTreeMap<String, FieldKind>,
+    /// The vault TOTP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp:               Option<PlaintextTotp>,
+    /// The vault password policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy:             Option<PasswordPolicy>,
+    /// When the password was last changed, seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_changed:   Option<u64>,
+    /// The vault attachments, their data is base64 encoded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments:        Vec<Attachment>,
+    /// The vault item type with its fields
+    #[serde(default, skip_serializing_if = "Item::is_login")]
+    pub item:               Item,
+}
+
+/// A plaintext vault TOTP
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaintextTotp {
+    /// The base32 TOTP secret
+    pub secret:    String,
+    /// The TOTP hash function
+    #[serde(default)]
+    pub algorithm: TotpAlgorithm,
+    /// The TOTP code digits, from 6 to 8
+    #[serde(default = "default_digits")]
+    pub digits:    u8,
+}
+
+/// The TOTP hash function, as named in the `otpauth://` URIs
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    /// The SHA1 hash function
+    #[default]
+    Sha1,
+    /// The SHA256 hash function
+    Sha256,
+    /// The SHA512 hash function
+    Sha512,
+}
+
+/// The default TOTP digits of the plaintext TOTP
+const fn default_digits() -> u8 {
+    super::otpauth::DEFAULT_DIGITS
+}
+
+impl From<TotpHash> for TotpAlgorithm {
+    fn from(hash: TotpHash) -> Self {
+        match hash {
+            TotpHash::Sha1 => Self::Sha1,
+            TotpHash::Sha256 => Self::Sha256,
+            TotpHash::Sha512 => Self::Sha512,
+        }
+    }
+}
+
+impl From<TotpAlgorithm> for TotpHash {
+    fn from(algorithm: TotpAlgorithm) -> Self {
+        match algorithm {
+            TotpAlgorithm::Sha1 => Self::Sha1,
+            TotpAlgorithm::Sha256 => Self::Sha256,
+            TotpAlgorithm::Sha512 => Self::Sha512,
+        }
+    }
+}
+
+impl From<&Vault> for PlaintextVault {
+    fn from(vault: &Vault) -> Self {
+        Self {
+            name:               vault.name.clone(),
+            username:           vault.username.clone(),
+            password:           vault.password.clone(),
+            service:            vault.service.clone(),
+            note:               vault.note.clone(),
+            folder:             vault.folder.clone(),
+            custom_fields:      vault.custom_fields.clone(),
+            custom_field_kinds: vault.custom_field_kinds.clone(),
+            totp:               vault.totp_secret.as_ref().map(|secret| {
+                PlaintextTotp {
+                    secret:    secret.clone(),
+                    algorithm: vault.totp_hash.clone().into(),
+                    digits:    vault.totp_digits,
+                }
+            }),
+            policy:             vault.policy.clone(),
+            password_changed:   vault.password_changed,
+            attachments:        vault.attachments.clone(),
+            item:               vault.item.clone(),
+        }
+    }
+}
+
+impl PlaintextVault {
+    /// Convert the plaintext vault to a vault
+    ///
+    /// ## Errors
+    /// - If the TOTP digits isn't between 6 and 8
+    fn into_vault(self) -> LprsResult<Vault> {
+        let (totp_secret, totp_hash, totp_digits) = match self.totp {
+            Some(totp) => {
+                if !(6..=8).contains(&totp.digits) {
+                    return Err(LprsError::Other(format!(
+                        "The `{}` vault TOTP digits must be between 6 and 8, found {}",
+                        self.name, totp.digits
+                    )));
+                }
+                (Some(totp.secret), totp.algorithm.into(), totp.digits)
+            }
+            None => (None, TotpHash::default(), default_digits()),
+        };
+
+        Ok(Vault {
+            folder: self.folder,
+            policy: self.policy,
+            password_changed: self.password_changed,
+            attachments: self.attachments,
+            item: self.item,
+            custom_field_kinds: self.custom_field_kinds,
+            ..Vault::new(
+                self.name,
+                self.username,
+                self.password,
+                self.service,
+                self.note,
+                self.custom_fields,
+                totp_secret,
+                totp_hash,
+                totp_digits,
+            )
+        })
+    }
+}
+
+/// Returns true if the data is a plaintext export, checks only its `format`
+pub fn is_plaintext(data: &[u8]) -> bool {
+    /// The export format, to detect the plaintext export
+    #[derive(Deserialize)]
+    struct FormatOnly {
+        format: String,
+    }
+
+    serde_json::from_slice::<FormatOnly>(data).is_ok_and(|f| f.format == PLAINTEXT_FORMAT)
+}
+
+/// Export the vaults as plaintext JSON
+///
+/// ## Errors
+/// - If the serialization failed
+pub fn export(vaults: &[Vault]) -> LprsResult<String> {
+    serde_json::to_string_pretty(&PlaintextExport {
+        schema:  Some(PLAINTEXT_SCHEMA.to_owned()),
+        format:  PLAINTEXT_FORMAT.to_owned(),
+        version: PLAINTEXT_VERSION,
+        vaults:  vaults.iter().map(PlaintextVault::from).collect(),
+    })
+    .map_err(Into::into)
+}
+
+/// Parse the plaintext JSON export and returns its vaults
+///
+/// ## Errors
+/// - If the data is not valid plaintext export
+/// - If the export version is newer than the supported one
+/// - If a vault have invalid TOTP digits or a reserved custom field
+/// - If a vault attachments are invalid or too large
+/// - If a vault item fields are invalid
+/// - If a vault custom field is invalid for its kind
+pub fn import(json_data: &[u8]) -> LprsResult<Vec<Vault>> {
+    let export: PlaintextExport = serde_json::from_slice(json_data)?;
+    if export.format != PLAINTEXT_FORMAT {
+        return Err(LprsError::Other(format!(
+            "Unknown plaintext export format `{}`, expected `{PLAINTEXT_FORMAT}`",
+            export.format
+        )));
+    }
+    if export.version > PLAINTEXT_VERSION {
+        return Err(LprsError::Other(format!(
+            "The plaintext export version {} is newer than the supported version \
+             {PLAINTEXT_VERSION}, update lprs to import it",
+            export.version
+        )));
+    }
+
+    let vaults = export
+        .vaults
+        .into_iter()
+        .map(PlaintextVault::into_vault)
+        .collect::<LprsResult<Vec<_>>>()?;
+    vaults.iter().try_for_each(Vault::validate)?;
+    Ok(vaults)
+}
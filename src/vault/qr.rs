@@ -0,0 +1,130 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+use std::{
+    fs,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use qrcode::{Color, QrCode, render::unicode::Dense1x2};
+
+use crate::{LprsError, LprsResult, utils};
+
+/// The size of a single QR module in the PNG image, in pixels
+const PNG_MODULE_SIZE: usize = 8;
+/// The quiet zone around the QR code in the PNG image, in modules
+const PNG_QUIET_ZONE: usize = 4;
+
+/// Encode the given data as a QR code
+fn encode(data: &str) -> LprsResult<QrCode> {
+    QrCode::new(data.as_bytes()).map_err(|err| LprsError::Qr(err.to_string()))
+}
+
+/// Render the given data as a QR code using the unicode half-blocks, each
+/// character is two QR modules.
+///
+/// The colors are inverted, so the code can be scanned from a dark terminal.
+///
+/// ## Errors
+/// - If the data is too long to fit in a QR code
+pub fn terminal_qr(data: &str) -> LprsResult<String> {
+    Ok(encode(data)?
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+/// Write the given data as a QR code to a grayscale PNG image
+///
+/// ## Errors
+/// - If the data is too long to fit in a QR code
+/// - If can't create or write to the file
+pub fn write_png(data: &str, path: &Path) -> LprsResult<()> {
+    let code = encode(data)?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = (modules + PNG_QUIET_ZONE * 2) * PNG_MODULE_SIZE;
+
+    let pixels: Vec<u8> = (0..size * size)
+        .map(|idx| {
+            let (x, y) = (
+                (idx % size) / PNG_MODULE_SIZE,
+                (idx / size) / PNG_MODULE_SIZE,
+            );
+            let is_dark = (PNG_QUIET_ZONE..modules + PNG_QUIET_ZONE).contains(&x)
+                && (PNG_QUIET_ZONE..modules + PNG_QUIET_ZONE).contains(&y)
+                && colors[(y - PNG_QUIET_ZONE) * modules + (x - PNG_QUIET_ZONE)] == Color::Dark;
+            if is_dark { 0x00 } else { 0xFF }
+        })
+        .collect();
+
+    let size = u32::try_from(size).map_err(|err| LprsError::Qr(err.to_string()))?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(utils::create_private_file(path)?),
+        size,
+        size,
+    );
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|err| LprsError::Qr(err.to_string()))
+}
+
+/// Read the QR code from the given PNG image and returns its content
+///
+/// ## Errors
+/// - If can't read the file
+/// - If the file isn't a valid PNG image
+/// - If there is no QR code in the image or it can't be decoded
+pub fn read_png(path: &Path) -> LprsResult<String> {
+    let mut decoder = png::Decoder::new(BufReader::new(fs::File::open(path)?));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|err| LprsError::Qr(format!("Invalid PNG image: {err}")))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|err| LprsError::Qr(format!("Invalid PNG image: {err}")))?;
+
+    let samples = info.color_type.samples();
+    let (width, height) = (info.width as usize, info.height as usize);
+    let luma = |x: usize, y: usize| {
+        let pixel = &buf[y * info.line_size + x * samples..][..samples];
+        match info.color_type {
+            png::ColorType::Rgb | png::ColorType::Rgba => {
+                ((u32::from(pixel[0]) * 299
+                    + u32::from(pixel[1]) * 587
+                    + u32::from(pixel[2]) * 114)
+                    / 1000) as u8
+            }
+            _ => pixel[0],
+        }
+    };
+
+    let mut image = rqrr::PreparedImage::prepare_from_greyscale(width, height, luma);
+    image
+        .detect_grids()
+        .first()
+        .ok_or_else(|| LprsError::Qr("There is no QR code in the given image".to_owned()))?
+        .decode()
+        .map(|(_, content)| content)
+        .map_err(|err| LprsError::Qr(format!("Can't decode the QR code: {err}")))
+}
@@ -0,0 +1,142 @@
+// Lprs - A local CLI vaults manager. For human and machine use
+// Copyright (C) 2024 Awiteb <a@4rs.nl>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://gnu.org/licenses/gpl-3.0.html>.
+
+//! The passwords strength, scored with the same scorer of the master password
+
+use std::fmt;
+
+use clap::Args;
+use passwords::{analyzer, scorer};
+
+use crate::{LprsError, LprsResult};
+
+/// The strength of a password
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    /// The password score, from 0 to 100
+    pub score:     f64,
+    /// The password length in characters
+    pub length:    usize,
+    /// Whether the password is a common password
+    pub is_common: bool,
+}
+
+impl Strength {
+    /// Analyze and score the password
+    pub fn of(password: &str) -> Self {
+        let analyzed = analyzer::analyze(password);
+        Self {
+            score:     scorer::score(&analyzed),
+            length:    analyzed.length(),
+            is_common: analyzed.is_common(),
+        }
+    }
+
+    /// Returns the score rating, as described by the scorer
+    pub fn rating(&self) -> &'static str {
+        match self.score {
+            score if score < 20.0 => "very dangerous",
+            score if score < 40.0 => "dangerous",
+            score if score < 60.0 => "very weak",
+            score if score < 80.0 => "weak",
+            score if score < 90.0 => "good",
+            score if score < 95.0 => "strong",
+            score if score < 99.0 => "very strong",
+            _ => "invulnerable",
+        }
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0} ({})", self.score, self.rating())?;
+        if self.is_common {
+            write!(f, ", common password")?;
+        }
+        Ok(())
+    }
+}
+
+/// The minimum strength of the vaults passwords
+#[derive(Debug, Clone, Args)]
+pub struct StrengthPolicy {
+    /// The minimum score of the vault password, from 0 to 100
+    #[arg(long, value_name = "SCORE", default_value_t = 60.0)]
+    pub min_score:  f64,
+    /// The minimum length of the vault password
+    #[arg(long, value_name = "LENGTH", default_value_t = 8)]
+    pub min_length: usize,
+}
+
+impl StrengthPolicy {
+    /// Returns why the password is weak, `None` if it's strong enough
+    pub fn weakness(&self, password: &str) -> Option<String> {
+        let strength = Strength::of(password);
+        if strength.is_common {
+            Some("The password is a common password".to_owned())
+        } else if strength.length < self.min_length {
+            Some(format!(
+                "The password length is {}, the minimum is {}",
+                strength.length, self.min_length
+            ))
+        } else if strength.score < self.min_score {
+            Some(format!(
+                "The password score is {strength}, the minimum is {}",
+                self.min_score
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Check the password strength. In the interactive mode a weak password
+    /// is a warning, the user is asked whether to use it anyway
+    ///
+    /// ## Errors
+    /// - If the password is weak, and the user refused it in the interactive
+    ///   mode
+    /// - If can't read the user input
+    pub fn enforce(&self, password: &str, interactive: bool) -> LprsResult<()> {
+        let Some(weakness) = self.weakness(password) else {
+            return Ok(());
+        };
+        if interactive {
+            eprintln!("Warning: {weakness}");
+            if inquire::Confirm::new("Use this weak password anyway?")
+                .with_default(false)
+                .prompt()?
+            {
+                return Ok(());
+            }
+        }
+        Err(LprsError::WeakPassword(format!(
+            "{weakness}, use `--force` to use it anyway"
+        )))
+    }
+
+    /// Validate the policy values
+    ///
+    /// ## Errors
+    /// - If the minimum score isn't between 0 and 100
+    pub fn validate(&self) -> LprsResult<()> {
+        if !(0.0..=100.0).contains(&self.min_score) {
+            return Err(LprsError::Other(
+                "The minimum score must be between 0 and 100".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}